@@ -0,0 +1,17 @@
+use std::io;
+use std::process::ExitCode;
+
+use rust_chess::UciEngine;
+
+// The rust-chess engine for GUIs and tournament managers speaking UCI over stdin and stdout
+fn main() -> ExitCode {
+    let mut engine = UciEngine::new(io::stdout());
+
+    match engine.run(io::stdin().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use std::io;
+use std::process::ExitCode;
+
+use rust_chess::XBoardEngine;
+
+// The rust-chess engine for XBoard, WinBoard and other programs speaking CECP over stdin and stdout
+fn main() -> ExitCode {
+    let mut engine = XBoardEngine::new(io::stdout());
+
+    match engine.run(io::stdin().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,28 +1,40 @@
+use crate::error::FenError;
+use crate::move_::Move;
+use crate::move_validator::{
+    BLACK_PAWN_ATTACKS, KING_ATTACKS, KNIGHT_MOVES, MoveValidator, WHITE_PAWN_ATTACKS,
+};
+use crate::types::{algebraic_to_square, square_to_algebraic, Color, PieceType};
+use crate::zobrist;
 
-pub(crate) struct Board {
-    pub(crate) white_pawns: u64,
-    pub(crate) white_knights: u64,
-    pub(crate) white_bishops: u64,
-    pub(crate) white_rooks: u64,
-    pub(crate) white_queen: u64,
-    pub(crate) white_king: u64,
-    pub(crate) black_pawns: u64,
-    pub(crate) black_knights: u64,
-    pub(crate) black_bishops: u64,
-    pub(crate) black_rooks: u64,
-    pub(crate) black_queen: u64,
-    pub(crate) black_king: u64,
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Board {
+    white_pawns: u64,
+    white_knights: u64,
+    white_bishops: u64,
+    white_rooks: u64,
+    white_queen: u64,
+    white_king: u64,
+    black_pawns: u64,
+    black_knights: u64,
+    black_bishops: u64,
+    black_rooks: u64,
+    black_queen: u64,
+    black_king: u64,
     white_kingside_castling: bool,
     white_queenside_castling: bool,
     black_kingside_castling: bool,
     black_queenside_castling: bool,
-    pub(crate) en_passant_square: Option<u8>,
+    en_passant_square: Option<u8>,
+    // Zobrist key of the position, including the side to move
+    hash: u64,
 }
 
 impl Board {
 
-    pub(crate) fn new() -> Self {
-        Self {
+    pub fn new() -> Self {
+        let mut board = Self {
             white_pawns: 0x000000000000FF00,
             white_knights: 0x0000000000000042,
             white_bishops: 0x0000000000000024,
@@ -40,24 +52,723 @@ impl Board {
             black_kingside_castling: true,
             black_queenside_castling: true,
             en_passant_square: None,
-        }
+            hash: 0,
+        };
+
+        board.hash = board.compute_hash(Color::White);
+        board
     }
     
-    pub(crate) fn white_pieces(&self) -> u64 {
+    // Reads the piece placement, castling rights and en passant square of a FEN string.
+    // The side to move and the move counters are kept by Game, see Game::from_fen.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(FenError::FieldCount(fields.len()));
+        }
+
+        let mut board = Self {
+            white_pawns: 0,
+            white_knights: 0,
+            white_bishops: 0,
+            white_rooks: 0,
+            white_queen: 0,
+            white_king: 0,
+            black_pawns: 0,
+            black_knights: 0,
+            black_bishops: 0,
+            black_rooks: 0,
+            black_queen: 0,
+            black_king: 0,
+            white_kingside_castling: false,
+            white_queenside_castling: false,
+            black_kingside_castling: false,
+            black_queenside_castling: false,
+            en_passant_square: None,
+            hash: 0,
+        };
+
+        // Piece placement, from rank 8 down to rank 1
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+
+        for (index, rank_text) in ranks.iter().enumerate() {
+            let rank = 7 - index as u8;
+            let mut file = 0u8;
+
+            for c in rank_text.chars() {
+                if let Some(empty) = c.to_digit(10).filter(|n| (1..=8).contains(n)) {
+                    file += empty as u8;
+                } else if let Some(piece_type) = PieceType::from_char(c) {
+                    if file >= 8 {
+                        return Err(FenError::RankLength { rank: rank + 1, text: rank_text.to_string() });
+                    }
+                    let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+                    *board.pieces_mut(color, piece_type) |= 1u64 << (rank * 8 + file);
+                    file += 1;
+                } else {
+                    return Err(FenError::InvalidPiece { rank: rank + 1, character: c });
+                }
+            }
+
+            if file != 8 {
+                return Err(FenError::RankLength { rank: rank + 1, text: rank_text.to_string() });
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            let kings = board.pieces(color, PieceType::King).count_ones();
+            if kings != 1 {
+                return Err(FenError::KingCount { color, count: kings });
+            }
+        }
+
+        if (board.white_pawns | board.black_pawns) & 0xFF000000000000FF != 0 {
+            return Err(FenError::PawnOnBackRank);
+        }
+
+        // Active color, only validated here
+        let turn = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(FenError::ActiveColor(other.to_string())),
+        };
+
+        // Castling availability
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                let right = match c {
+                    'K' => &mut board.white_kingside_castling,
+                    'Q' => &mut board.white_queenside_castling,
+                    'k' => &mut board.black_kingside_castling,
+                    'q' => &mut board.black_queenside_castling,
+                    _ => return Err(FenError::Castling(fields[2].to_string())),
+                };
+                if *right {
+                    return Err(FenError::Castling(fields[2].to_string()));
+                }
+                *right = true;
+            }
+        }
+
+        // En passant target square, which must be on the 6th rank for white and the 3rd for black.
+        // The pawn that just moved two squares stands in front of it, and the squares it left are empty.
+        if fields[3] != "-" {
+            let square = algebraic_to_square(fields[3])
+                .ok_or_else(|| FenError::EnPassant(fields[3].to_string()))?;
+            let expected_rank = match turn {
+                Color::White => 5,
+                Color::Black => 2,
+            };
+            if square / 8 != expected_rank {
+                return Err(FenError::EnPassant(fields[3].to_string()));
+            }
+            let pushed_pawn = Self::en_passant_victim(square, turn);
+            let origin = Self::en_passant_victim(square, turn.opponent());
+            if board.all_pieces() & (1u64 << square | 1u64 << origin) != 0
+                || board.pieces(turn.opponent(), PieceType::Pawn) & (1u64 << pushed_pawn) == 0
+            {
+                return Err(FenError::EnPassant(fields[3].to_string()));
+            }
+            // As in make_move, the square is only recorded when a pawn can capture there
+            if board.can_capture_en_passant(square, turn) {
+                board.en_passant_square = Some(square);
+            }
+        }
+
+        // The side that just moved cannot have left its king in check
+        if board.in_check(turn.opponent()) {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        board.hash = board.compute_hash(turn);
+
+        Ok(board)
+    }
+
+    // Writes the first four FEN fields; Game::to_fen appends the move counters
+    pub fn to_fen(&self, turn: Color) -> String {
+        let mut placement = String::new();
+
+        for rank in (0..8u8).rev() {
+            let mut empty = 0;
+            for file in 0..8u8 {
+                match self.piece_at(rank * 8 + file) {
+                    Some((color, piece_type)) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        let c = piece_type.to_char();
+                        placement.push(if color == Color::White { c.to_ascii_uppercase() } else { c });
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                placement.push('/');
+            }
+        }
+
+        let mut castling = String::new();
+        for (right, c) in [
+            (self.white_kingside_castling, 'K'),
+            (self.white_queenside_castling, 'Q'),
+            (self.black_kingside_castling, 'k'),
+            (self.black_queenside_castling, 'q'),
+        ] {
+            if right {
+                castling.push(c);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let active_color = match turn {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let en_passant = self.en_passant_square.map_or("-".to_string(), square_to_algebraic);
+
+        format!("{} {} {} {}", placement, active_color, castling, en_passant)
+    }
+
+    pub fn white_pieces(&self) -> u64 {
         self.white_pawns | self.white_rooks |self.white_knights | 
         self.white_bishops | self.white_queen | self.white_king
     }
 
-    pub(crate) fn black_pieces(&self) -> u64 {
+    pub fn black_pieces(&self) -> u64 {
         self.black_pawns | self.black_rooks | self.black_knights |
         self.black_bishops | self.black_queen | self.black_king
     }
 
-    pub(crate) fn all_pieces(&self) -> u64 {
+    pub fn all_pieces(&self) -> u64 {
         self.white_pawns | self.white_rooks | self.white_knights |
         self.white_bishops | self.white_queen | self.white_king |
         self.black_pawns | self.black_rooks | self.black_knights |
         self.black_bishops | self.black_queen | self.black_king
     }
 
-}
\ No newline at end of file
+    pub fn en_passant_square(&self) -> Option<u8> {
+        self.en_passant_square
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    // Computes the Zobrist key from scratch; make_move keeps it up to date incrementally
+    pub(crate) fn compute_hash(&self, turn: Color) -> u64 {
+        let mut hash = self.castling_hash();
+
+        for color in [Color::White, Color::Black] {
+            for piece_type in [
+                PieceType::Pawn,
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+                PieceType::King,
+            ] {
+                let mut pieces = self.pieces(color, piece_type);
+                while pieces != 0 {
+                    hash ^= zobrist::piece_key(color, piece_type, pieces.trailing_zeros() as u8);
+                    pieces &= pieces - 1;
+                }
+            }
+        }
+
+        if let Some(square) = self.en_passant_square {
+            hash ^= zobrist::en_passant_key(square);
+        }
+
+        if turn == Color::Black {
+            hash ^= zobrist::black_to_move_key();
+        }
+
+        hash
+    }
+
+    fn castling_hash(&self) -> u64 {
+        [
+            self.white_kingside_castling,
+            self.white_queenside_castling,
+            self.black_kingside_castling,
+            self.black_queenside_castling,
+        ]
+        .iter()
+        .enumerate()
+        .filter(|(_, right)| **right)
+        .fold(0, |hash, (index, _)| hash ^ zobrist::castling_key(index))
+    }
+
+    pub fn color_pieces(&self, color: Color) -> u64 {
+        match color {
+            Color::White => self.white_pieces(),
+            Color::Black => self.black_pieces(),
+        }
+    }
+
+    pub fn pieces(&self, color: Color, piece_type: PieceType) -> u64 {
+        match (color, piece_type) {
+            (Color::White, PieceType::Pawn) => self.white_pawns,
+            (Color::White, PieceType::Knight) => self.white_knights,
+            (Color::White, PieceType::Bishop) => self.white_bishops,
+            (Color::White, PieceType::Rook) => self.white_rooks,
+            (Color::White, PieceType::Queen) => self.white_queen,
+            (Color::White, PieceType::King) => self.white_king,
+            (Color::Black, PieceType::Pawn) => self.black_pawns,
+            (Color::Black, PieceType::Knight) => self.black_knights,
+            (Color::Black, PieceType::Bishop) => self.black_bishops,
+            (Color::Black, PieceType::Rook) => self.black_rooks,
+            (Color::Black, PieceType::Queen) => self.black_queen,
+            (Color::Black, PieceType::King) => self.black_king,
+        }
+    }
+
+    fn pieces_mut(&mut self, color: Color, piece_type: PieceType) -> &mut u64 {
+        match (color, piece_type) {
+            (Color::White, PieceType::Pawn) => &mut self.white_pawns,
+            (Color::White, PieceType::Knight) => &mut self.white_knights,
+            (Color::White, PieceType::Bishop) => &mut self.white_bishops,
+            (Color::White, PieceType::Rook) => &mut self.white_rooks,
+            (Color::White, PieceType::Queen) => &mut self.white_queen,
+            (Color::White, PieceType::King) => &mut self.white_king,
+            (Color::Black, PieceType::Pawn) => &mut self.black_pawns,
+            (Color::Black, PieceType::Knight) => &mut self.black_knights,
+            (Color::Black, PieceType::Bishop) => &mut self.black_bishops,
+            (Color::Black, PieceType::Rook) => &mut self.black_rooks,
+            (Color::Black, PieceType::Queen) => &mut self.black_queen,
+            (Color::Black, PieceType::King) => &mut self.black_king,
+        }
+    }
+
+    pub fn piece_at(&self, square: u8) -> Option<(Color, PieceType)> {
+        let bit = 1u64 << square;
+
+        for color in [Color::White, Color::Black] {
+            for piece_type in [
+                PieceType::Pawn,
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+                PieceType::King,
+            ] {
+                if self.pieces(color, piece_type) & bit != 0 {
+                    return Some((color, piece_type));
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn can_castle_kingside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_kingside_castling,
+            Color::Black => self.black_kingside_castling,
+        }
+    }
+
+    pub fn can_castle_queenside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_queenside_castling,
+            Color::Black => self.black_queenside_castling,
+        }
+    }
+
+    // Bitboard of the pieces of both colors that attack the given square
+    pub fn attackers_to(&self, square: u8) -> u64 {
+        let sq = square as usize;
+        let all_pieces = self.all_pieces();
+
+        let knights = self.white_knights | self.black_knights;
+        let kings = self.white_king | self.black_king;
+        let rooks = self.white_rooks | self.black_rooks | self.white_queen | self.black_queen;
+        let bishops = self.white_bishops | self.black_bishops | self.white_queen | self.black_queen;
+
+        // A pawn attacks this square if a pawn of the other color on this square would attack it
+        (BLACK_PAWN_ATTACKS[sq] & self.white_pawns)
+            | (WHITE_PAWN_ATTACKS[sq] & self.black_pawns)
+            | (KNIGHT_MOVES[sq] & knights)
+            | (KING_ATTACKS[sq] & kings)
+            | (MoveValidator::rook_attacks(square, all_pieces) & rooks)
+            | (MoveValidator::bishop_attacks(square, all_pieces) & bishops)
+    }
+
+    pub fn is_square_attacked(&self, square: u8, by_color: Color) -> bool {
+        self.attackers_to(square) & self.color_pieces(by_color) != 0
+    }
+
+    pub fn in_check(&self, color: Color) -> bool {
+        let king = self.pieces(color, PieceType::King);
+        king != 0 && self.is_square_attacked(king.trailing_zeros() as u8, color.opponent())
+    }
+
+    // Neither side can ever checkmate: bare kings, a single minor piece, or only bishops
+    // that all stand on squares of the same color
+    pub fn has_insufficient_material(&self) -> bool {
+        let heavy_pieces = self.white_pawns | self.black_pawns | self.white_rooks | self.black_rooks |
+            self.white_queen | self.black_queen;
+        if heavy_pieces != 0 {
+            return false;
+        }
+
+        let knights = self.white_knights | self.black_knights;
+        let bishops = self.white_bishops | self.black_bishops;
+        let dark_squares = 0xAA55AA55AA55AA55u64;
+
+        match knights.count_ones() {
+            0 => bishops & dark_squares == 0 || bishops & !dark_squares == 0,
+            1 => bishops == 0,
+            _ => false,
+        }
+    }
+
+    // Whether the given side could checkmate by some series of legal moves. A bare king never
+    // can; any other material can, as the other side's pieces may block its king in, unless
+    // the material on the board rules out a mate for both sides.
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        self.color_pieces(color) != self.pieces(color, PieceType::King) && !self.has_insufficient_material()
+    }
+
+    // Plays a pseudo-legal move: captures, en passant victims, promotions, the rook of a
+    // castling move, castling rights and the en passant square are all updated here.
+    // The returned record restores the previous position through unmake_move.
+    pub fn make_move(&mut self, move_: &Move) -> UndoInfo {
+        let from = move_.from();
+        let to = move_.to();
+
+        let mut undo = UndoInfo {
+            captured: None,
+            is_en_passant: false,
+            white_kingside_castling: self.white_kingside_castling,
+            white_queenside_castling: self.white_queenside_castling,
+            black_kingside_castling: self.black_kingside_castling,
+            black_queenside_castling: self.black_queenside_castling,
+            en_passant_square: self.en_passant_square,
+            hash: self.hash,
+        };
+
+        let Some((color, piece_type)) = self.piece_at(from) else {
+            return undo;
+        };
+
+        // Remove the captured piece, if any
+        if let Some((captured_color, captured_type)) = self.piece_at(to) {
+            self.toggle_piece(captured_color, captured_type, to);
+            undo.captured = Some(captured_type);
+        }
+
+        // A pawn moving diagonally onto the en passant square captures the pawn behind it
+        if piece_type == PieceType::Pawn && self.en_passant_square == Some(to) && from % 8 != to % 8 {
+            let victim = Self::en_passant_victim(to, color);
+            // toggle_piece would put a pawn there instead; from_fen and make_move only record an
+            // en passant square behind a pawn that has just moved two squares
+            debug_assert!(
+                self.pieces(color.opponent(), PieceType::Pawn) & (1u64 << victim) != 0,
+                "no pawn to capture en passant on {}",
+                square_to_algebraic(victim)
+            );
+            self.toggle_piece(color.opponent(), PieceType::Pawn, victim);
+            undo.captured = Some(PieceType::Pawn);
+            undo.is_en_passant = true;
+        }
+
+        self.toggle_piece(color, piece_type, from);
+        self.toggle_piece(color, move_.promotion().unwrap_or(piece_type), to);
+
+        if move_.is_castling() {
+            let (rook_from, rook_to) = Self::castling_rook_squares(to);
+            self.toggle_piece(color, PieceType::Rook, rook_from);
+            self.toggle_piece(color, PieceType::Rook, rook_to);
+        }
+
+        if let Some(square) = self.en_passant_square {
+            self.hash ^= zobrist::en_passant_key(square);
+        }
+
+        // A double pawn push leaves the square it skipped open to en passant. It is only recorded
+        // when an enemy pawn can capture there, so that otherwise identical positions compare equal.
+        self.en_passant_square = None;
+        if piece_type == PieceType::Pawn && from.abs_diff(to) == 16 {
+            let skipped = (from + to) / 2;
+            if self.can_capture_en_passant(skipped, color.opponent()) {
+                self.en_passant_square = Some(skipped);
+                self.hash ^= zobrist::en_passant_key(skipped);
+            }
+        }
+
+        // Moving the king or a rook, or capturing a rook on its corner, loses the castling right
+        self.hash ^= self.castling_hash();
+        self.clear_castling_rights(from);
+        self.clear_castling_rights(to);
+        self.hash ^= self.castling_hash();
+
+        self.hash ^= zobrist::black_to_move_key();
+
+        debug_assert_eq!(
+            self.hash,
+            self.compute_hash(color.opponent()),
+            "incremental Zobrist key out of sync after {}",
+            move_
+        );
+
+        undo
+    }
+
+    pub fn unmake_move(&mut self, move_: &Move, undo: &UndoInfo) {
+        let from = move_.from();
+        let to = move_.to();
+
+        let Some((color, placed_type)) = self.piece_at(to) else {
+            return;
+        };
+
+        // A promoted piece goes back to being a pawn
+        let piece_type = if move_.promotion().is_some() { PieceType::Pawn } else { placed_type };
+
+        *self.pieces_mut(color, placed_type) &= !(1u64 << to);
+        *self.pieces_mut(color, piece_type) |= 1u64 << from;
+
+        if move_.is_castling() {
+            let (rook_from, rook_to) = Self::castling_rook_squares(to);
+            let rooks = self.pieces_mut(color, PieceType::Rook);
+            *rooks = (*rooks & !(1u64 << rook_to)) | (1u64 << rook_from);
+        }
+
+        if let Some(captured_type) = undo.captured {
+            let captured_square = if undo.is_en_passant { Self::en_passant_victim(to, color) } else { to };
+            *self.pieces_mut(color.opponent(), captured_type) |= 1u64 << captured_square;
+        }
+
+        self.white_kingside_castling = undo.white_kingside_castling;
+        self.white_queenside_castling = undo.white_queenside_castling;
+        self.black_kingside_castling = undo.black_kingside_castling;
+        self.black_queenside_castling = undo.black_queenside_castling;
+        self.en_passant_square = undo.en_passant_square;
+        self.hash = undo.hash;
+    }
+
+    // Adds or removes a piece, keeping the Zobrist key in step
+    fn toggle_piece(&mut self, color: Color, piece_type: PieceType, square: u8) {
+        *self.pieces_mut(color, piece_type) ^= 1u64 << square;
+        self.hash ^= zobrist::piece_key(color, piece_type, square);
+    }
+
+    // Whether a pawn of the given color stands next to the pawn that skipped the given square
+    fn can_capture_en_passant(&self, square: u8, color: Color) -> bool {
+        // A pawn attacks this square if a pawn of the other color on this square would attack it
+        let capturers = match color {
+            Color::White => BLACK_PAWN_ATTACKS[square as usize],
+            Color::Black => WHITE_PAWN_ATTACKS[square as usize],
+        };
+        capturers & self.pieces(color, PieceType::Pawn) != 0
+    }
+
+    // Square of the pawn captured by an en passant move landing on the given square
+    fn en_passant_victim(to: u8, color: Color) -> u8 {
+        match color {
+            Color::White => to - 8,
+            Color::Black => to + 8,
+        }
+    }
+
+    // Rook origin and destination for a castling move landing on the given king square
+    fn castling_rook_squares(king_to: u8) -> (u8, u8) {
+        match king_to {
+            6 => (7, 5),    // White kingside (h1 -> f1)
+            2 => (0, 3),    // White queenside (a1 -> d1)
+            62 => (63, 61), // Black kingside (h8 -> f8)
+            _ => (56, 59),  // Black queenside (a8 -> d8)
+        }
+    }
+
+    fn clear_castling_rights(&mut self, square: u8) {
+        match square {
+            4 => {
+                self.white_kingside_castling = false;
+                self.white_queenside_castling = false;
+            }
+            60 => {
+                self.black_kingside_castling = false;
+                self.black_queenside_castling = false;
+            }
+            7 => self.white_kingside_castling = false,
+            0 => self.white_queenside_castling = false,
+            63 => self.black_kingside_castling = false,
+            56 => self.black_queenside_castling = false,
+            _ => {}
+        }
+    }
+
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Everything make_move cannot recover from the position it leaves behind
+#[derive(Clone, Copy, Debug)]
+pub struct UndoInfo {
+    captured: Option<PieceType>,
+    is_en_passant: bool,
+    white_kingside_castling: bool,
+    white_queenside_castling: bool,
+    black_kingside_castling: bool,
+    black_queenside_castling: bool,
+    en_passant_square: Option<u8>,
+    hash: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fen_round_trips() {
+        for (fen, turn) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -", Color::White),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -", Color::White),
+            ("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3", Color::Black),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6", Color::White),
+            // The side to move may be in check
+            ("4k3/4R3/8/8/8/8/8/4K3 b - -", Color::Black),
+        ] {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(turn), fen);
+        }
+
+        assert_eq!(Board::from_fen(STARTING_FEN), Ok(Board::new()));
+    }
+
+    fn bits(squares: &[&str]) -> u64 {
+        squares.iter().fold(0, |bitboard, name| bitboard | 1u64 << algebraic_to_square(name).unwrap())
+    }
+
+    fn attackers(fen: &str, square: &str) -> u64 {
+        Board::from_fen(fen).unwrap().attackers_to(algebraic_to_square(square).unwrap())
+    }
+
+    #[test]
+    fn pawns_attack_diagonally_forward() {
+        let fen = "4k3/8/8/4p3/4P3/8/8/4K3 w - -";
+
+        assert_eq!(attackers(fen, "d5"), bits(&["e4"]));
+        assert_eq!(attackers(fen, "f5"), bits(&["e4"]));
+        assert_eq!(attackers(fen, "d4"), bits(&["e5"]));
+        assert_eq!(attackers(fen, "f4"), bits(&["e5"]));
+        // Neither straight ahead nor backwards
+        assert_eq!(attackers(fen, "e5"), 0);
+        assert_eq!(attackers(fen, "d3"), 0);
+        assert_eq!(attackers(fen, "d6"), 0);
+
+        assert!(Board::from_fen("4k3/3P4/8/8/8/8/8/4K3 b - -").unwrap().in_check(Color::Black));
+        assert!(Board::from_fen("4k3/8/8/8/8/8/3p4/4K3 w - -").unwrap().in_check(Color::White));
+        assert!(!Board::from_fen("4k3/8/8/8/8/8/3P4/4K3 w - -").unwrap().in_check(Color::White));
+    }
+
+    #[test]
+    fn knights_and_kings_attack_without_wrapping() {
+        let fen = "4k3/8/8/8/8/8/8/N3K2n w - -";
+
+        assert_eq!(attackers(fen, "b3"), bits(&["a1"]));
+        assert_eq!(attackers(fen, "c2"), bits(&["a1"]));
+        assert_eq!(attackers(fen, "g3"), bits(&["h1"]));
+        assert_eq!(attackers(fen, "f2"), bits(&["h1", "e1"]));
+        assert_eq!(attackers(fen, "d1"), bits(&["e1"]));
+        // One file across the board edge
+        assert_eq!(attackers(fen, "h3"), 0);
+        assert_eq!(attackers(fen, "a3"), 0);
+        assert_eq!(attackers(fen, "a2") & bits(&["h1"]), 0);
+    }
+
+    #[test]
+    fn sliders_stop_at_the_first_piece() {
+        let fen = "4k3/8/8/8/1q6/8/3P4/R1B1K3 w - -";
+
+        assert_eq!(attackers(fen, "a8"), bits(&["a1"]));
+        assert_eq!(attackers(fen, "b1"), bits(&["a1", "b4"]));
+        // The bishop on c1 blocks the rook, and the pawn on d2 blocks both the bishop and the queen
+        assert_eq!(attackers(fen, "d1"), bits(&["e1"]));
+        assert_eq!(attackers(fen, "e3") & bits(&["c1", "b4"]), 0);
+        assert_eq!(attackers(fen, "d2"), bits(&["c1", "e1", "b4"]));
+        assert_eq!(attackers(fen, "h4"), bits(&["b4"]));
+        assert!(!Board::from_fen(fen).unwrap().in_check(Color::White));
+
+        // Without the pawn the queen checks along the diagonal, and a rook along the back rank
+        assert!(Board::from_fen("4k3/8/8/8/1q6/8/8/R1B1K3 w - -").unwrap().in_check(Color::White));
+        assert!(Board::from_fen("R3k3/8/8/8/8/8/8/4K3 b - -").unwrap().in_check(Color::Black));
+        assert!(!Board::from_fen("R2nk3/8/8/8/8/8/8/4K3 b - -").unwrap().in_check(Color::Black));
+        assert!(Board::from_fen("4k3/8/8/7B/8/8/8/4K3 b - -").unwrap().in_check(Color::Black));
+    }
+
+    #[test]
+    fn en_passant_captures_the_pawn_that_moved() {
+        let mut board = Board::from_fen("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1").unwrap();
+        let start = board.clone();
+
+        let push = Move::new(51, 35, None, false);
+        let push_undo = board.make_move(&push);
+        assert_eq!(board.en_passant_square(), Some(43));
+
+        let capture = Move::new(36, 43, None, false);
+        let capture_undo = board.make_move(&capture);
+        assert_eq!(board.to_fen(Color::Black), "4k3/8/3P4/8/8/8/8/4K3 b - -");
+        assert_eq!(board.hash(), board.compute_hash(Color::Black));
+
+        board.unmake_move(&capture, &capture_undo);
+        board.unmake_move(&push, &push_undo);
+        assert_eq!(board, start);
+    }
+
+    #[test]
+    fn uncapturable_en_passant_squares_are_left_out() {
+        let mut board = Board::from_fen("4k3/3p4/8/P7/8/8/8/4K3 b - - 0 1").unwrap();
+        board.make_move(&Move::new(51, 35, None, false));
+
+        let loaded = Board::from_fen("4k3/8/8/P2p4/8/8/8/4K3 w - d6").unwrap();
+        assert_eq!(loaded.en_passant_square(), None);
+        assert_eq!(loaded.hash(), board.hash());
+        assert_eq!(loaded, board);
+    }
+
+    #[test]
+    fn rejects_invalid_fens() {
+        let en_passant = |square: &str| Err(FenError::EnPassant(square.to_string()));
+
+        for (fen, error) in [
+            ("8/8/8/8/8/8/8/8", Err(FenError::FieldCount(1))),
+            ("4k3/8/8/8/8/8/4K3 w - -", Err(FenError::RankCount(7))),
+            ("4k3/9/8/8/8/8/8/4K3 w - -", Err(FenError::InvalidPiece { rank: 7, character: '9' })),
+            ("4k3/8/8/8/8/8/8/4K4 w - -", Err(FenError::RankLength { rank: 1, text: "4K4".to_string() })),
+            ("8/8/8/8/8/8/8/4K3 w - -", Err(FenError::KingCount { color: Color::Black, count: 0 })),
+            ("4k3/8/8/8/8/8/8/3KK3 w - -", Err(FenError::KingCount { color: Color::White, count: 2 })),
+            ("4k2P/8/8/8/8/8/8/4K3 w - -", Err(FenError::PawnOnBackRank)),
+            ("4k3/8/8/8/8/8/8/4K3 x - -", Err(FenError::ActiveColor("x".to_string()))),
+            ("4k3/8/8/8/8/8/8/4K3 w KK -", Err(FenError::Castling("KK".to_string()))),
+            // Only the side to move may be in check: the other king could be captured
+            ("4k3/4R3/8/8/8/8/8/4K3 w - -", Err(FenError::OpponentInCheck)),
+            // On the wrong rank, without the pawn that moved, on an occupied square or with the
+            // square the pawn came from still occupied
+            ("4k3/8/8/8/4P3/8/8/4K3 w - e3", en_passant("e3")),
+            ("4k3/8/8/4P3/8/8/8/4K3 w - d6", en_passant("d6")),
+            ("4k3/8/3n4/3pP3/8/8/8/4K3 w - d6", en_passant("d6")),
+            ("4k3/3n4/8/3pP3/8/8/8/4K3 w - d6", en_passant("d6")),
+            ("4k3/8/8/8/3pP3/8/8/4K3 b - d3", en_passant("d3")),
+            ("4k3/8/8/8/8/8/8/4K3 w - z9", en_passant("z9")),
+        ] {
+            assert_eq!(Board::from_fen(fen), error, "{}", fen);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::move_::Move;
+use crate::types::Color;
+
+// A FEN string that cannot be loaded, by the field that is wrong
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FenError {
+    FieldCount(usize),
+    RankCount(usize),
+    // A rank that does not describe exactly 8 squares; ranks are numbered 1 to 8
+    RankLength { rank: u8, text: String },
+    InvalidPiece { rank: u8, character: char },
+    KingCount { color: Color, count: u32 },
+    PawnOnBackRank,
+    // The side not to move is in check, so its king could be captured
+    OpponentInCheck,
+    ActiveColor(String),
+    Castling(String),
+    EnPassant(String),
+    HalfmoveClock(String),
+    FullmoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(found) => write!(f, "FEN must have between 4 and 6 fields, found {}", found),
+            FenError::RankCount(found) => write!(f, "Piece placement must have 8 ranks, found {}", found),
+            FenError::RankLength { rank, text } => write!(f, "Rank {} must describe 8 squares: '{}'", rank, text),
+            FenError::InvalidPiece { rank, character } => {
+                write!(f, "Invalid character '{}' in rank {}", character, rank)
+            }
+            FenError::KingCount { color, count } => write!(f, "Expected exactly one {} king, found {}", color, count),
+            FenError::PawnOnBackRank => write!(f, "Pawns cannot stand on the first or eighth rank"),
+            FenError::OpponentInCheck => write!(f, "The side not to move cannot be in check"),
+            FenError::ActiveColor(field) => write!(f, "Active color must be 'w' or 'b', found '{}'", field),
+            FenError::Castling(field) => write!(f, "Invalid castling availability '{}'", field),
+            FenError::EnPassant(field) => write!(f, "Invalid en passant square '{}'", field),
+            FenError::HalfmoveClock(field) => {
+                write!(f, "Halfmove clock must be a non-negative number, found '{}'", field)
+            }
+            FenError::FullmoveNumber(field) => write!(f, "Fullmove number must be a positive number, found '{}'", field),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+// A move in UCI or SAN notation that does not resolve to exactly one legal move
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NotationError {
+    Malformed { notation: String, reason: &'static str },
+    Illegal { notation: String, color: Color },
+    // The SAN of every legal move the notation could stand for
+    Ambiguous { notation: String, candidates: Vec<String> },
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::Malformed { notation, reason } => write!(f, "Invalid move '{}': {}", notation, reason),
+            NotationError::Illegal { notation, color } => write!(f, "Illegal move '{}' for {}", notation, color),
+            NotationError::Ambiguous { notation, candidates } => {
+                write!(f, "Ambiguous move '{}', candidates: {}", notation, candidates.join(", "))
+            }
+        }
+    }
+}
+
+impl Error for NotationError {}
+
+// Why a move cannot be played in a position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IllegalMoveReason {
+    NoPieceOnSource,
+    WrongSideToMove,
+    // The piece cannot move that way, or the destination is blocked
+    InvalidMovement,
+    CastlingNotAllowed,
+    LeavesKingInCheck,
+}
+
+impl fmt::Display for IllegalMoveReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            IllegalMoveReason::NoPieceOnSource => "there is no piece on the source square",
+            IllegalMoveReason::WrongSideToMove => "the piece does not belong to the side to move",
+            IllegalMoveReason::InvalidMovement => "the piece cannot move there",
+            IllegalMoveReason::CastlingNotAllowed => "castling is not allowed",
+            IllegalMoveReason::LeavesKingInCheck => "it leaves the king in check",
+        };
+        f.write_str(reason)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GameError {
+    GameOver,
+    IllegalMove { move_: Move, reason: IllegalMoveReason },
+    NoDrawToClaim,
+    NothingToUndo,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::GameOver => write!(f, "The game is already over"),
+            GameError::IllegalMove { move_, reason } => write!(f, "Illegal move {}: {}", move_, reason),
+            GameError::NoDrawToClaim => write!(f, "No draw can be claimed in this position"),
+            GameError::NothingToUndo => write!(f, "There is no move to take back"),
+        }
+    }
+}
+
+impl Error for GameError {}
+
+// A game of a PGN file that cannot be read. Games are numbered from 1, as are plies.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PgnError {
+    Io { game: usize, error: io::Error },
+    Syntax { game: usize, message: String },
+    InvalidFen { game: usize, error: FenError },
+    Movetext { game: usize, ply: usize, message: &'static str },
+    IllegalMove { game: usize, ply: usize, san: String, error: NotationError },
+}
+
+impl PgnError {
+    pub fn game(&self) -> usize {
+        match self {
+            PgnError::Io { game, .. }
+            | PgnError::Syntax { game, .. }
+            | PgnError::InvalidFen { game, .. }
+            | PgnError::Movetext { game, .. }
+            | PgnError::IllegalMove { game, .. } => *game,
+        }
+    }
+
+    pub fn ply(&self) -> Option<usize> {
+        match self {
+            PgnError::Movetext { ply, .. } | PgnError::IllegalMove { ply, .. } => Some(*ply),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::Io { game, error } => write!(f, "Game {}: failed to read PGN: {}", game, error),
+            PgnError::Syntax { game, message } => write!(f, "Game {}: {}", game, message),
+            PgnError::InvalidFen { game, error } => write!(f, "Game {}: invalid FEN tag: {}", game, error),
+            PgnError::Movetext { game, ply, message } => write!(f, "Game {}, ply {}: {}", game, ply, message),
+            PgnError::IllegalMove { game, ply, san, error } => {
+                write!(f, "Game {}, ply {}: cannot play '{}': {}", game, ply, san, error)
+            }
+        }
+    }
+}
+
+impl Error for PgnError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PgnError::Io { error, .. } => Some(error),
+            PgnError::InvalidFen { error, .. } => Some(error),
+            PgnError::IllegalMove { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
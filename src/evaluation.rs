@@ -0,0 +1,470 @@
+use std::fmt;
+
+use crate::board::Board;
+use crate::move_validator::{MoveValidator, KING_ATTACKS, KNIGHT_MOVES};
+use crate::types::{Color, PieceType};
+
+// Game phase of the full set of minor and major pieces; it falls towards 0 as they come off
+const MAX_PHASE: i32 = 24;
+
+const PIECE_TYPES: [PieceType; 6] =
+    [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King];
+
+// Middlegame and endgame values, indexed by PieceType
+const MATERIAL: [(i32, i32); 6] = [(100, 120), (320, 300), (330, 320), (500, 530), (900, 950), (0, 0)];
+const PHASE_WEIGHT: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+// Per move of a knight, bishop, rook and queen beyond its typical number of moves
+const MOBILITY_WEIGHT: [(i32, i32); 6] = [(0, 0), (4, 4), (5, 5), (2, 4), (1, 2), (0, 0)];
+const TYPICAL_MOBILITY: [i32; 6] = [0, 4, 6, 6, 12, 0];
+
+// King safety counts in the middlegame only
+const PAWN_SHIELD_BONUS: i32 = 10;
+const KING_ZONE_ATTACK_WEIGHT: [i32; 6] = [0, 2, 2, 3, 5, 0];
+
+const DOUBLED_PAWN: (i32, i32) = (-10, -20);
+const ISOLATED_PAWN: (i32, i32) = (-10, -15);
+// By rank as seen from the pawn's own side, from the first rank to the eighth
+const PASSED_PAWN: [(i32, i32); 8] = [(0, 0), (5, 10), (10, 20), (15, 35), (25, 60), (40, 90), (60, 130), (0, 0)];
+const BISHOP_PAIR: (i32, i32) = (30, 50);
+
+// Piece-square tables from white's point of view, written with rank 8 on top: a white
+// piece on square s reads entry s ^ 56 and a black piece reads entry s
+#[rustfmt::skip]
+const PAWN_TABLE_MG: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const PAWN_TABLE_EG: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    80, 80, 80, 80, 80, 80, 80, 80,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    30, 30, 30, 30, 30, 30, 30, 30,
+    20, 20, 20, 20, 20, 20, 20, 20,
+    10, 10, 10, 10, 10, 10, 10, 10,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_TABLE_MG: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+#[rustfmt::skip]
+const KING_TABLE_EG: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+const FILE_A: u64 = 0x0101010101010101;
+
+// One part of the evaluation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EvalTerm {
+    Material,
+    PieceSquares,
+    Mobility,
+    KingSafety,
+    PawnStructure,
+    BishopPair,
+}
+
+impl EvalTerm {
+    pub const ALL: [EvalTerm; 6] = [
+        EvalTerm::Material,
+        EvalTerm::PieceSquares,
+        EvalTerm::Mobility,
+        EvalTerm::KingSafety,
+        EvalTerm::PawnStructure,
+        EvalTerm::BishopPair,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EvalTerm::Material => "Material",
+            EvalTerm::PieceSquares => "Piece squares",
+            EvalTerm::Mobility => "Mobility",
+            EvalTerm::KingSafety => "King safety",
+            EvalTerm::PawnStructure => "Pawn structure",
+            EvalTerm::BishopPair => "Bishop pair",
+        }
+    }
+}
+
+// The static evaluation of a position, term by term and side by side, in centipawns.
+// Every term blends a middlegame and an endgame score by the game phase.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Evaluation {
+    phase: i32,
+    // Tapered score of every term for white and black
+    terms: [[i32; 2]; 6],
+}
+
+impl Evaluation {
+    pub fn new(board: &Board) -> Self {
+        let phase = PIECE_TYPES
+            .iter()
+            .map(|&piece_type| {
+                let count = board.pieces(Color::White, piece_type) | board.pieces(Color::Black, piece_type);
+                count.count_ones() as i32 * PHASE_WEIGHT[piece_type as usize]
+            })
+            .sum::<i32>()
+            .min(MAX_PHASE);
+
+        let mut terms = [[0; 2]; 6];
+        for color in [Color::White, Color::Black] {
+            let scores = [
+                material(board, color),
+                piece_squares(board, color),
+                mobility(board, color),
+                king_safety(board, color),
+                pawn_structure(board, color),
+                bishop_pair(board, color),
+            ];
+            for (index, (mg, eg)) in scores.into_iter().enumerate() {
+                terms[index][color as usize] = (mg * phase + eg * (MAX_PHASE - phase)) / MAX_PHASE;
+            }
+        }
+
+        Self { phase, terms }
+    }
+
+    // From 24 with all pieces on the board down to 0 with only kings and pawns
+    pub fn phase(&self) -> i32 {
+        self.phase
+    }
+
+    // What the term is worth to one side
+    pub fn term(&self, term: EvalTerm, color: Color) -> i32 {
+        self.terms[term as usize][color as usize]
+    }
+
+    // What the term is worth to `color` over its opponent
+    pub fn term_balance(&self, term: EvalTerm, color: Color) -> i32 {
+        self.term(term, color) - self.term(term, color.opponent())
+    }
+
+    // The score of the position from the point of view of `color`
+    pub fn total(&self, color: Color) -> i32 {
+        EvalTerm::ALL.iter().map(|&term| self.term_balance(term, color)).sum()
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16}{:>8}{:>8}{:>8}", "Term", "White", "Black", "Total")?;
+        for term in EvalTerm::ALL {
+            writeln!(
+                f,
+                "{:<16}{:>8}{:>8}{:>8}",
+                term.name(),
+                self.term(term, Color::White),
+                self.term(term, Color::Black),
+                self.term_balance(term, Color::White)
+            )?;
+        }
+        write!(f, "{:<16}{:>24}", "Total (white)", self.total(Color::White))
+    }
+}
+
+// The score of the position from the point of view of `color`, see Evaluation for the terms
+pub fn evaluate(board: &Board, color: Color) -> i32 {
+    Evaluation::new(board).total(color)
+}
+
+// Calls `f` with every square set in the bitboard
+fn for_each_square(mut bitboard: u64, mut f: impl FnMut(u8)) {
+    while bitboard != 0 {
+        f(bitboard.trailing_zeros() as u8);
+        bitboard &= bitboard - 1;
+    }
+}
+
+fn material(board: &Board, color: Color) -> (i32, i32) {
+    PIECE_TYPES.iter().fold((0, 0), |(mg, eg), &piece_type| {
+        let count = board.pieces(color, piece_type).count_ones() as i32;
+        let (piece_mg, piece_eg) = MATERIAL[piece_type as usize];
+        (mg + count * piece_mg, eg + count * piece_eg)
+    })
+}
+
+fn piece_squares(board: &Board, color: Color) -> (i32, i32) {
+    let mut score = (0, 0);
+
+    for piece_type in PIECE_TYPES {
+        let (table_mg, table_eg) = match piece_type {
+            PieceType::Pawn => (&PAWN_TABLE_MG, &PAWN_TABLE_EG),
+            PieceType::Knight => (&KNIGHT_TABLE, &KNIGHT_TABLE),
+            PieceType::Bishop => (&BISHOP_TABLE, &BISHOP_TABLE),
+            PieceType::Rook => (&ROOK_TABLE, &ROOK_TABLE),
+            PieceType::Queen => (&QUEEN_TABLE, &QUEEN_TABLE),
+            PieceType::King => (&KING_TABLE_MG, &KING_TABLE_EG),
+        };
+
+        for_each_square(board.pieces(color, piece_type), |square| {
+            let index = match color {
+                Color::White => square ^ 56,
+                Color::Black => square,
+            } as usize;
+            score.0 += table_mg[index];
+            score.1 += table_eg[index];
+        });
+    }
+
+    score
+}
+
+fn piece_attacks(piece_type: PieceType, square: u8, occupied: u64) -> u64 {
+    match piece_type {
+        PieceType::Knight => KNIGHT_MOVES[square as usize],
+        PieceType::Bishop => MoveValidator::bishop_attacks(square, occupied),
+        PieceType::Rook => MoveValidator::rook_attacks(square, occupied),
+        PieceType::Queen => {
+            MoveValidator::bishop_attacks(square, occupied) | MoveValidator::rook_attacks(square, occupied)
+        }
+        PieceType::Pawn | PieceType::King => 0,
+    }
+}
+
+// Squares a piece attacks that are not occupied by its own side
+fn mobility(board: &Board, color: Color) -> (i32, i32) {
+    let occupied = board.all_pieces();
+    let own = board.color_pieces(color);
+    let mut score = (0, 0);
+
+    for piece_type in [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen] {
+        let (weight_mg, weight_eg) = MOBILITY_WEIGHT[piece_type as usize];
+        for_each_square(board.pieces(color, piece_type), |square| {
+            let moves = (piece_attacks(piece_type, square, occupied) & !own).count_ones() as i32;
+            let extra = moves - TYPICAL_MOBILITY[piece_type as usize];
+            score.0 += extra * weight_mg;
+            score.1 += extra * weight_eg;
+        });
+    }
+
+    score
+}
+
+// Pawns sheltering the king and enemy pieces bearing on the squares around it
+fn king_safety(board: &Board, color: Color) -> (i32, i32) {
+    let king = board.pieces(color, PieceType::King);
+    if king == 0 {
+        return (0, 0);
+    }
+    let king_square = king.trailing_zeros() as u8;
+    let king_file = king_square % 8;
+    let king_rank = king_square / 8;
+
+    // Own pawns up to two ranks in front of the king, on its file and the neighbouring ones
+    let mut shield = 0u64;
+    for file in king_file.saturating_sub(1)..=(king_file + 1).min(7) {
+        for step in 1..=2u8 {
+            let rank = match color {
+                Color::White => king_rank.checked_add(step).filter(|&rank| rank < 8),
+                Color::Black => king_rank.checked_sub(step),
+            };
+            if let Some(rank) = rank {
+                shield |= 1u64 << (rank * 8 + file);
+            }
+        }
+    }
+    let shield_pawns = (board.pieces(color, PieceType::Pawn) & shield).count_ones() as i32;
+
+    let zone = KING_ATTACKS[king_square as usize] | king;
+    let occupied = board.all_pieces();
+    let mut attack_weight = 0;
+    for piece_type in [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen] {
+        for_each_square(board.pieces(color.opponent(), piece_type), |square| {
+            let attacked = (piece_attacks(piece_type, square, occupied) & zone).count_ones() as i32;
+            attack_weight += attacked * KING_ZONE_ATTACK_WEIGHT[piece_type as usize];
+        });
+    }
+
+    (shield_pawns * PAWN_SHIELD_BONUS - attack_weight, 0)
+}
+
+fn pawn_structure(board: &Board, color: Color) -> (i32, i32) {
+    let pawns = board.pieces(color, PieceType::Pawn);
+    let enemy_pawns = board.pieces(color.opponent(), PieceType::Pawn);
+    let mut score = (0, 0);
+
+    for file in 0..8u8 {
+        let on_file = (pawns & (FILE_A << file)).count_ones() as i32;
+        if on_file > 1 {
+            score.0 += (on_file - 1) * DOUBLED_PAWN.0;
+            score.1 += (on_file - 1) * DOUBLED_PAWN.1;
+        }
+    }
+
+    for_each_square(pawns, |square| {
+        let file = square % 8;
+        let rank = square / 8;
+        let adjacent_files = adjacent_files(file);
+
+        if pawns & adjacent_files == 0 {
+            score.0 += ISOLATED_PAWN.0;
+            score.1 += ISOLATED_PAWN.1;
+        }
+
+        // Passed when no enemy pawn stands ahead of it on its own or a neighbouring file
+        let ahead = match color {
+            Color::White => u64::MAX.checked_shl((rank as u32 + 1) * 8).unwrap_or(0),
+            Color::Black => (1u64 << (rank * 8)) - 1,
+        };
+        if enemy_pawns & ahead & (adjacent_files | FILE_A << file) == 0 {
+            let relative_rank = match color {
+                Color::White => rank,
+                Color::Black => 7 - rank,
+            };
+            let (bonus_mg, bonus_eg) = PASSED_PAWN[relative_rank as usize];
+            score.0 += bonus_mg;
+            score.1 += bonus_eg;
+        }
+    });
+
+    score
+}
+
+fn adjacent_files(file: u8) -> u64 {
+    let mut files = 0;
+    if file > 0 {
+        files |= FILE_A << (file - 1);
+    }
+    if file < 7 {
+        files |= FILE_A << (file + 1);
+    }
+    files
+}
+
+fn bishop_pair(board: &Board, color: Color) -> (i32, i32) {
+    if board.pieces(color, PieceType::Bishop).count_ones() >= 2 { BISHOP_PAIR } else { (0, 0) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate_fen(fen: &str) -> Evaluation {
+        Evaluation::new(&Board::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn symmetric_positions_are_level() {
+        let evaluation = evaluate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(evaluation.phase(), MAX_PHASE);
+        assert_eq!(evaluation.total(Color::White), 0);
+        assert_eq!(evaluation.term(EvalTerm::Material, Color::White), 4000);
+
+        // The same position with the colors swapped scores the other way around
+        let white = evaluate_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4");
+        let black = evaluate_fen("rnbqk2r/pppp1ppp/5n2/2b1p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R b KQkq - 4 4");
+        assert_eq!(white.total(Color::White), black.total(Color::Black));
+        for term in EvalTerm::ALL {
+            assert_eq!(white.term(term, Color::White), black.term(term, Color::Black), "{:?}", term);
+        }
+    }
+
+    #[test]
+    fn terms_add_up() {
+        let evaluation = evaluate_fen("r1bq1rk1/pp3ppp/2nbpn2/3p4/2PP4/2N1PN2/PP2BPPP/R2QKB1R w KQ - 0 8");
+        let sum: i32 = EvalTerm::ALL.iter().map(|&term| evaluation.term_balance(term, Color::White)).sum();
+
+        assert_eq!(evaluation.total(Color::White), sum);
+        assert_eq!(evaluation.total(Color::Black), -sum);
+        assert_eq!(evaluate(&Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap(), Color::White), 0);
+        assert!(evaluation.to_string().contains("Pawn structure"));
+    }
+
+    #[test]
+    fn scores_pawn_structure_and_bishops() {
+        // A passed pawn on the sixth rank against doubled, isolated pawns
+        let evaluation = evaluate_fen("4k3/8/P7/8/8/2p5/2p5/4K3 w - - 0 1");
+        assert_eq!(evaluation.phase(), 0);
+        assert_eq!(evaluation.term(EvalTerm::PawnStructure, Color::White), -15 + 90);
+        assert_eq!(evaluation.term(EvalTerm::PawnStructure, Color::Black), -20 - 30 + 90 + 130);
+
+        let evaluation = evaluate_fen("2b1kb2/8/8/8/8/8/8/2B1KN2 w - - 0 1");
+        assert_eq!(evaluation.term(EvalTerm::BishopPair, Color::White), 0);
+        assert!(evaluation.term(EvalTerm::BishopPair, Color::Black) > 0);
+    }
+
+    #[test]
+    fn exposed_kings_are_penalised() {
+        let sheltered = evaluate_fen("r1bq1rk1/ppp2ppp/2n2n2/3pp3/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1");
+        let exposed = evaluate_fen("r1bq1rk1/ppp2p1p/2n2n2/3pp3/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1");
+        assert!(
+            sheltered.term(EvalTerm::KingSafety, Color::Black) > exposed.term(EvalTerm::KingSafety, Color::Black)
+        );
+    }
+}
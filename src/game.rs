@@ -1,15 +1,37 @@
-use crate::board::Board;
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, UndoInfo, STARTING_FEN};
+use crate::error::{FenError, GameError};
 use crate::move_::Move;
 use crate::move_validator::MoveValidator;
-use crate::player::Player;
+use crate::pgn;
+use crate::player::{Player, PlayerAction, PlayerView};
 use crate::types::Color;
+use crate::types::DrawReason;
 use crate::types::GameResult;
+use crate::types::GameStatus;
+use crate::types::PieceType;
+use crate::types::Termination;
+use crate::types::TimeControl;
 
-struct Game {
+pub struct Game {
     board: Board,
+    start_fen: String,
     turn: Color,
     move_history: Vec<Move>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    // What unmaking each move of the history needs, with the halfmove clock before it
+    undo_history: Vec<(UndoInfo, u32)>,
+    // Zobrist key of every position reached, for repetition detection
+    position_history: Vec<u64>,
     result: Option<GameResult>,
+    termination: Option<Termination>,
+    time_control: Option<TimeControl>,
+    // Remaining time of white and black, only used with a time control
+    time_left: [Duration; 2],
+    // Whether step claims threefold repetition and fifty-move draws for the players
+    automatic_draw_claims: bool,
     white_player: Box<dyn Player>,
     black_player: Box<dyn Player>,
     move_validator: MoveValidator,
@@ -17,49 +39,647 @@ struct Game {
 
 impl Game {
 
-    fn new(white_player: Box<dyn Player>, black_player: Box<dyn Player>) -> Self {
+    pub fn new(white_player: Box<dyn Player>, black_player: Box<dyn Player>) -> Self {
         Self {
             board: Board::new(),
+            start_fen: STARTING_FEN.to_string(),
             turn: Color::White,
             move_history: Vec::new(),
+            undo_history: Vec::new(),
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            position_history: vec![Board::new().hash()],
             result: None,
+            termination: None,
+            time_control: None,
+            time_left: [Duration::ZERO; 2],
+            automatic_draw_claims: true,
             white_player,
             black_player,
             move_validator: MoveValidator::new()
         }
     }
 
-    fn get_game_result(&self) -> &Option<GameResult> {
-        &self.result
+    pub fn from_fen(fen: &str, white_player: Box<dyn Player>, black_player: Box<dyn Player>) -> Result<Self, FenError> {
+        let board = Board::from_fen(fen)?;
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+
+        let turn = match fields[1] {
+            "w" => Color::White,
+            _ => Color::Black,
+        };
+
+        // The move counters are optional and default to the values of a fresh game
+        let halfmove_clock = match fields.get(4) {
+            Some(field) => field
+                .parse::<u32>()
+                .map_err(|_| FenError::HalfmoveClock(field.to_string()))?,
+            None => 0,
+        };
+
+        let fullmove_number = match fields.get(5) {
+            Some(field) => field
+                .parse::<u32>()
+                .ok()
+                .filter(|&n| n >= 1)
+                .ok_or_else(|| FenError::FullmoveNumber(field.to_string()))?,
+            None => 1,
+        };
+
+        let start_fen = format!("{} {} {}", board.to_fen(turn), halfmove_clock, fullmove_number);
+
+        let position = board.hash();
+
+        let mut game = Self {
+            board,
+            start_fen,
+            turn,
+            move_history: Vec::new(),
+            undo_history: Vec::new(),
+            halfmove_clock,
+            fullmove_number,
+            position_history: vec![position],
+            result: None,
+            termination: None,
+            time_control: None,
+            time_left: [Duration::ZERO; 2],
+            automatic_draw_claims: true,
+            white_player,
+            black_player,
+            move_validator: MoveValidator::new()
+        };
+
+        // The position may already be decided
+        game.update_result();
+
+        Ok(game)
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    pub fn move_history(&self) -> &[Move] {
+        &self.move_history
+    }
+
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn to_fen(&self) -> String {
+        format!("{} {} {}", self.board.to_fen(self.turn), self.halfmove_clock, self.fullmove_number)
+    }
+
+    // Exports the game as PGN; `tags` fill in the Seven Tag Roster and may add custom tags
+    pub fn to_pgn(&self, tags: &[(&str, &str)]) -> String {
+        pgn::write_pgn(&self.start_fen, &self.move_history, self.result.as_ref(), tags)
+    }
+
+    pub fn get_game_result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    pub fn get_termination(&self) -> Option<Termination> {
+        self.termination
+    }
+
+    // The rule that drew the game, if it ended in a draw
+    pub fn get_draw_reason(&self) -> Option<DrawReason> {
+        match self.termination {
+            Some(Termination::Draw(reason)) => Some(reason),
+            _ => None,
+        }
+    }
+
+    // Starts both clocks afresh; step charges the player to move for the time spent choosing
+    pub fn set_time_control(&mut self, time_control: TimeControl) {
+        self.time_control = Some(time_control);
+        self.time_left = [time_control.initial(); 2];
+    }
+
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+
+    pub fn time_left(&self, color: Color) -> Option<Duration> {
+        self.time_control.map(|_| self.time_left[color as usize])
+    }
+
+    pub fn set_automatic_draw_claims(&mut self, enabled: bool) {
+        self.automatic_draw_claims = enabled;
     }
 
-    fn is_over(&self) -> bool {
+    // A draw the side to move could claim now under the threefold repetition or fifty-move rule
+    pub fn get_draw_claim(&self) -> Option<DrawReason> {
+        if self.is_over() {
+            None
+        } else if self.repetition_count() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove_clock >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    pub fn claim_draw(&mut self) -> Result<DrawReason, GameError> {
+        let reason = self.get_draw_claim().ok_or(GameError::NoDrawToClaim)?;
+        self.end_in_draw(reason);
+        Ok(reason)
+    }
+
+    pub fn resign(&mut self, color: Color) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+
+        self.end_with_win(color.opponent(), Termination::Resignation);
+        Ok(())
+    }
+
+    // Takes back the last move, reopening the game if that move ended it. The clocks are left as they are.
+    pub fn undo_move(&mut self) -> Result<Move, GameError> {
+        let (Some(move_), Some((undo, halfmove_clock))) = (self.move_history.pop(), self.undo_history.pop()) else {
+            return Err(GameError::NothingToUndo);
+        };
+
+        self.board.unmake_move(&move_, &undo);
+        self.position_history.pop();
+        self.halfmove_clock = halfmove_clock;
+        self.turn = self.turn.opponent();
+        if self.turn == Color::Black {
+            self.fullmove_number -= 1;
+        }
+        self.result = None;
+        self.termination = None;
+
+        Ok(move_)
+    }
+
+    pub fn is_over(&self) -> bool {
         self.result.is_some()
     }
 
-    fn get_current_player(&self) -> &Box<dyn Player> {
+    pub fn get_current_player(&self) -> &dyn Player {
         match self.turn {
-            Color::White => &self.white_player,
-            Color::Black => &self.black_player,
+            Color::White => self.white_player.as_ref(),
+            Color::Black => self.black_player.as_ref(),
+        }
+    }
+
+    pub fn get_status(&self) -> GameStatus {
+        let in_check = self.board.in_check(self.turn);
+        let has_legal_moves = !self.get_legal_moves().is_empty();
+
+        match (in_check, has_legal_moves) {
+            (true, false) => GameStatus::Checkmate,
+            (false, false) => GameStatus::Stalemate,
+            (true, true) => GameStatus::Check,
+            (false, true) => GameStatus::Normal,
         }
     }
 
-    fn get_legal_moves(&self) -> Vec<Move> {
+    pub fn get_legal_moves(&self) -> Vec<Move> {
         self.move_validator.get_legal_moves(&self.board, self.turn)
     }
 
-    fn apply_move(&self, move_: &Move) -> Result<(), String> {
-        if !self.move_validator.is_move_legal(&self.board, move_) {
-            return Err(format!("Illegal move selected: {:?}", move_));
+    // Asks the player to move for its action and carries it out
+    pub fn step(&mut self) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+
+        if self.automatic_draw_claims && self.get_draw_claim().is_some() {
+            return self.claim_draw().map(|_| ());
+        }
+
+        let started = Instant::now();
+        let action = self.ask_current_player(|player, view| player.select_move(view));
+
+        if !self.charge_clock(started.elapsed()) {
+            return Ok(());
+        }
+
+        match action {
+            PlayerAction::Move(move_) => self.play_timed_move(&move_),
+            PlayerAction::OfferDraw(move_) => {
+                self.play_timed_move(&move_)?;
+                if !self.is_over() && self.ask_current_player(|player, view| player.accept_draw(view)) {
+                    self.end_in_draw(DrawReason::Agreement);
+                }
+                Ok(())
+            }
+            PlayerAction::ClaimDraw => self.claim_draw().map(|_| ()),
+            PlayerAction::Resign => self.resign(self.turn),
+            PlayerAction::Undo => {
+                if self.move_history.len() < 2 {
+                    return Err(GameError::NothingToUndo);
+                }
+                self.undo_move()?;
+                self.undo_move().map(|_| ())
+            }
+        }
+    }
+
+    // Plays the game until it is decided and returns the result
+    pub fn play(&mut self) -> Result<GameResult, GameError> {
+        loop {
+            if let Some(result) = self.result {
+                return Ok(result);
+            }
+
+            self.step()?;
         }
+    }
 
-        // todo: implement validation.
+    pub fn apply_move(&mut self, move_: &Move) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+
+        self.move_validator
+            .validate_move(&self.board, move_, self.turn)
+            .map_err(|reason| GameError::IllegalMove { move_: *move_, reason })?;
+
+        // The halfmove clock restarts on every pawn move and capture
+        let is_pawn_move = matches!(self.board.piece_at(move_.from()), Some((_, PieceType::Pawn)));
+        let is_capture = self.board.piece_at(move_.to()).is_some();
+
+        let undo = self.board.make_move(move_);
+        self.undo_history.push((undo, self.halfmove_clock));
+        self.move_history.push(*move_);
+        self.position_history.push(self.board.hash());
+
+        self.halfmove_clock = if is_pawn_move || is_capture { 0 } else { self.halfmove_clock + 1 };
+        if self.turn == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.turn = self.turn.opponent();
+
+        self.update_result();
 
         Ok(())
     }
+
+    fn ask_current_player<T>(&mut self, ask: impl FnOnce(&mut dyn Player, &PlayerView) -> T) -> T {
+        let legal_moves = self.get_legal_moves();
+        let view = PlayerView {
+            board: &self.board,
+            turn: self.turn,
+            start_fen: &self.start_fen,
+            move_history: &self.move_history,
+            legal_moves: &legal_moves,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            draw_claim: self.get_draw_claim(),
+            time_control: self.time_control,
+            time_left: self.time_control.map(|_| self.time_left),
+        };
+        let player = match self.turn {
+            Color::White => self.white_player.as_mut(),
+            Color::Black => self.black_player.as_mut(),
+        };
+
+        ask(player, &view)
+    }
+
+    // Takes the thinking time off the clock of the side to move, ending the game if it ran out
+    fn charge_clock(&mut self, elapsed: Duration) -> bool {
+        if self.time_control.is_none() {
+            return true;
+        }
+
+        let time_left = &mut self.time_left[self.turn as usize];
+        match time_left.checked_sub(elapsed) {
+            Some(remaining) => {
+                *time_left = remaining;
+                true
+            }
+            None => {
+                *time_left = Duration::ZERO;
+                // A flag only loses against a side that could still checkmate (FIDE 6.9)
+                if self.board.has_mating_material(self.turn.opponent()) {
+                    self.end_with_win(self.turn.opponent(), Termination::Timeout);
+                } else {
+                    self.end_in_draw(DrawReason::InsufficientMaterial);
+                }
+                false
+            }
+        }
+    }
+
+    fn play_timed_move(&mut self, move_: &Move) -> Result<(), GameError> {
+        let mover = self.turn;
+        self.apply_move(move_)?;
+
+        if let Some(time_control) = self.time_control {
+            self.time_left[mover as usize] += time_control.increment();
+        }
+
+        Ok(())
+    }
+
+    // Ends the game on checkmate, stalemate or a draw that applies without being claimed
+    fn update_result(&mut self) {
+        match self.get_status() {
+            GameStatus::Checkmate => {
+                self.end_with_win(self.turn.opponent(), Termination::Checkmate);
+                return;
+            }
+            GameStatus::Stalemate => {
+                self.end_in_draw(DrawReason::Stalemate);
+                return;
+            }
+            GameStatus::Check | GameStatus::Normal => {}
+        }
+
+        if self.board.has_insufficient_material() {
+            self.end_in_draw(DrawReason::InsufficientMaterial);
+        } else if self.repetition_count() >= 5 {
+            self.end_in_draw(DrawReason::FivefoldRepetition);
+        } else if self.halfmove_clock >= 150 {
+            self.end_in_draw(DrawReason::SeventyFiveMoveRule);
+        }
+    }
+
+    fn end_in_draw(&mut self, reason: DrawReason) {
+        self.result = Some(GameResult::Draw);
+        self.termination = Some(Termination::Draw(reason));
+    }
+
+    fn end_with_win(&mut self, winner: Color, termination: Termination) {
+        self.result = match winner {
+            Color::White => Some(GameResult::WhiteWin),
+            Color::Black => Some(GameResult::BlackWin),
+        };
+        self.termination = Some(termination);
+    }
+
+    // How many times the current position has occurred, including now
+    fn repetition_count(&self) -> usize {
+        let current = self.position_history.last();
+        self.position_history.iter().filter(|position| Some(*position) == current).count()
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::IllegalMoveReason;
+    use crate::player::RandomPlayer;
+
+    // Plays the given actions in order and answers draw offers with `accepts_draws`
+    struct ScriptedPlayer {
+        actions: Vec<PlayerAction>,
+        accepts_draws: bool,
+        delay: Duration,
+    }
+
+    impl ScriptedPlayer {
+        fn new(ucis: &[&str]) -> Self {
+            let actions = ucis.iter().map(|uci| PlayerAction::Move(uci_move(uci))).collect();
+            Self { actions, accepts_draws: false, delay: Duration::ZERO }
+        }
+    }
+
+    impl Player for ScriptedPlayer {
+        fn select_move(&mut self, view: &PlayerView) -> PlayerAction {
+            std::thread::sleep(self.delay);
+            let action = self.actions.remove(0);
+            if let PlayerAction::Move(move_) | PlayerAction::OfferDraw(move_) = action {
+                assert!(view.legal_moves().contains(&move_));
+            }
+            action
+        }
+
+        fn accept_draw(&mut self, _view: &PlayerView) -> bool {
+            self.accepts_draws
+        }
+    }
+
+    // Resolves a UCI move without a board, for moves that are neither castling nor promotions
+    fn uci_move(uci: &str) -> Move {
+        let square = |name: &str| crate::types::algebraic_to_square(name).unwrap();
+        Move::new(square(&uci[0..2]), square(&uci[2..4]), None, false)
+    }
+
+    fn new_game(fen: &str) -> Game {
+        Game::from_fen(fen, Box::new(RandomPlayer), Box::new(RandomPlayer)).unwrap()
+    }
+
+    fn play_uci(game: &mut Game, ucis: &[&str]) {
+        for uci in ucis {
+            let move_ = Move::from_uci(uci, &game.board, game.turn).unwrap();
+            game.apply_move(&move_).unwrap();
+        }
+    }
+
+    #[test]
+    fn checkmate_ends_the_game() {
+        let mut game = new_game(STARTING_FEN);
+        play_uci(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+
+        assert_eq!(game.get_status(), GameStatus::Checkmate);
+        assert_eq!(game.result, Some(GameResult::BlackWin));
+        assert_eq!(game.step(), Err(GameError::GameOver));
+        assert_eq!(game.to_fen(), "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+    }
+
+    #[test]
+    fn illegal_moves_report_the_reason() {
+        let mut game = new_game("4k3/8/8/8/8/8/4r3/R3K2R w KQ - 0 1");
 
+        for (from, to, is_castling, reason) in [
+            (20, 28, false, IllegalMoveReason::NoPieceOnSource),
+            (12, 13, false, IllegalMoveReason::WrongSideToMove),
+            (0, 9, false, IllegalMoveReason::InvalidMovement),
+            (4, 6, true, IllegalMoveReason::CastlingNotAllowed),
+            (4, 13, false, IllegalMoveReason::LeavesKingInCheck),
+        ] {
+            let move_ = Move::new(from, to, None, is_castling);
+            assert_eq!(game.apply_move(&move_), Err(GameError::IllegalMove { move_, reason }));
+        }
+
+        let error = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - x 1", Box::new(RandomPlayer), Box::new(RandomPlayer));
+        assert!(matches!(error, Err(FenError::HalfmoveClock(field)) if field == "x"));
+    }
+
+    #[test]
+    fn decided_positions_have_a_result() {
+        let mut game = new_game("8/8/8/8/8/8/1q6/K1k5 w - - 0 1");
+        assert_eq!(game.play(), Ok(GameResult::BlackWin));
+
+        let mut game = new_game("7k/8/8/8/8/8/2q5/K7 w - - 0 1");
+        assert_eq!(game.get_status(), GameStatus::Stalemate);
+        assert_eq!(game.play(), Ok(GameResult::Draw));
+    }
+
+    #[test]
+    fn repetitions_draw_the_game() {
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+        let mut game = new_game(STARTING_FEN);
+        play_uci(&mut game, &shuffle);
+        play_uci(&mut game, &shuffle[..3]);
+        assert_eq!(game.get_draw_claim(), None);
+        play_uci(&mut game, &shuffle[3..]);
+        assert_eq!(game.get_draw_claim(), Some(DrawReason::ThreefoldRepetition));
+        assert!(!game.is_over());
+
+        game.step().unwrap();
+        assert_eq!(game.result, Some(GameResult::Draw));
+        assert_eq!(game.get_draw_reason(), Some(DrawReason::ThreefoldRepetition));
+
+        let mut game = new_game(STARTING_FEN);
+        play_uci(&mut game, &[shuffle, shuffle, shuffle, shuffle].concat());
+        assert_eq!(game.get_draw_reason(), Some(DrawReason::FivefoldRepetition));
+    }
+
+    #[test]
+    fn move_rules_draw_the_game() {
+        let mut game = new_game("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80");
+        play_uci(&mut game, &["a1a2"]);
+        assert_eq!(game.get_draw_claim(), Some(DrawReason::FiftyMoveRule));
+        assert_eq!(game.claim_draw(), Ok(DrawReason::FiftyMoveRule));
+        assert!(game.is_over());
+
+        let mut game = new_game("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 80");
+        play_uci(&mut game, &["a1a2"]);
+        assert_eq!(game.get_draw_reason(), Some(DrawReason::SeventyFiveMoveRule));
+
+        let mut game = new_game("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80");
+        play_uci(&mut game, &["e2e4"]);
+        assert_eq!(game.get_draw_claim(), None);
+    }
+
+    #[test]
+    fn dead_positions_draw_the_game() {
+        for fen in ["4k3/8/8/8/8/8/8/4K3 w - - 0 1", "4k3/8/8/8/8/8/8/4KN2 w - - 0 1", "2b1k3/8/8/8/8/8/8/4KB2 w - - 0 1"] {
+            assert_eq!(new_game(fen).get_draw_reason(), Some(DrawReason::InsufficientMaterial), "{}", fen);
+        }
+
+        for fen in ["1b2k3/8/8/8/8/8/8/4KB2 w - - 0 1", "4k3/8/8/8/8/8/8/4KNN1 w - - 0 1", "4kn2/8/8/8/8/8/8/4KB2 w - - 0 1"] {
+            assert!(!new_game(fen).is_over(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn players_see_the_position() {
+        struct Inspector;
 
+        impl Player for Inspector {
+            fn select_move(&mut self, view: &PlayerView) -> PlayerAction {
+                assert_eq!(view.turn(), Color::Black);
+                assert_eq!(view.move_history(), [uci_move("e2e4")]);
+                assert_eq!(view.start_fen(), STARTING_FEN);
+                assert_eq!(view.board().piece_at(28), Some((Color::White, PieceType::Pawn)));
+                assert_eq!(view.legal_moves().len(), 20);
+                // White spent a little of its minute and gained the increment
+                let white_time = view.time_left(Color::White).unwrap();
+                assert!(white_time > Duration::from_secs(61) && white_time <= Duration::from_secs(62));
+                PlayerAction::Resign
+            }
+        }
+
+        let mut game = Game::new(Box::new(ScriptedPlayer::new(&["e2e4"])), Box::new(Inspector));
+        game.set_time_control(TimeControl::new(Duration::from_secs(60), Duration::from_secs(2)));
+        game.step().unwrap();
+        game.step().unwrap();
+
+        assert_eq!(game.get_game_result(), Some(GameResult::WhiteWin));
+        assert_eq!(game.get_termination(), Some(Termination::Resignation));
+    }
+
+    #[test]
+    fn draw_offers_need_acceptance() {
+        let mut white = ScriptedPlayer::new(&[]);
+        white.actions.push(PlayerAction::OfferDraw(uci_move("e2e4")));
+        let mut black = ScriptedPlayer::new(&[]);
+        let mut game = Game::new(Box::new(white), Box::new(black));
+        game.step().unwrap();
+        assert!(!game.is_over());
+
+        white = ScriptedPlayer::new(&[]);
+        white.actions.push(PlayerAction::OfferDraw(uci_move("e2e4")));
+        black = ScriptedPlayer::new(&[]);
+        black.accepts_draws = true;
+        game = Game::new(Box::new(white), Box::new(black));
+        game.step().unwrap();
+        assert_eq!(game.get_draw_reason(), Some(DrawReason::Agreement));
+    }
+
+    #[test]
+    fn flagging_loses_the_game() {
+        let mut white = ScriptedPlayer::new(&["e2e4"]);
+        white.delay = Duration::from_millis(20);
+        let mut game = Game::new(Box::new(white), Box::new(RandomPlayer));
+        game.set_time_control(TimeControl::new(Duration::from_millis(5), Duration::ZERO));
+        game.step().unwrap();
+
+        assert_eq!(game.get_game_result(), Some(GameResult::BlackWin));
+        assert_eq!(game.get_termination(), Some(Termination::Timeout));
+        assert_eq!(game.time_left(Color::White), Some(Duration::ZERO));
+        assert!(game.move_history().is_empty());
+    }
+
+    #[test]
+    fn flagging_draws_against_a_bare_king() {
+        let mut white = ScriptedPlayer::new(&["e2e4"]);
+        white.delay = Duration::from_millis(20);
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", Box::new(white), Box::new(RandomPlayer)).unwrap();
+        game.set_time_control(TimeControl::new(Duration::from_millis(5), Duration::ZERO));
+        game.step().unwrap();
 
+        assert_eq!(game.get_game_result(), Some(GameResult::Draw));
+        assert_eq!(game.get_draw_reason(), Some(DrawReason::InsufficientMaterial));
+        assert_eq!(game.time_left(Color::White), Some(Duration::ZERO));
 
+        // Any material besides the king can still mate with the help of the other side
+        assert!(Board::from_fen("4k3/8/8/8/8/8/4P3/4KN2 w - -").unwrap().has_mating_material(Color::White));
+        assert!(!Board::from_fen("4k3/8/8/8/8/8/4P3/4KN2 w - -").unwrap().has_mating_material(Color::Black));
+        assert!(Board::from_fen("4kn2/8/8/8/8/8/4P3/4K3 w - -").unwrap().has_mating_material(Color::Black));
+        assert!(!Board::from_fen("4kn2/8/8/8/8/8/8/4K3 w - -").unwrap().has_mating_material(Color::Black));
+    }
+
+    #[test]
+    fn undo_restores_the_position() {
+        let mut game = new_game(STARTING_FEN);
+        play_uci(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert!(game.is_over());
+
+        assert_eq!(game.undo_move(), Ok(uci_move("d8h4")));
+        assert_eq!(game.undo_move(), Ok(uci_move("g2g4")));
+        assert!(!game.is_over());
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/8/5P2/PPPPP1PP/RNBQKBNR w KQkq - 0 2");
+
+        play_uci(&mut game, &["g1h3", "b8c6", "h3g1", "c6b8"]);
+        assert_eq!(game.halfmove_clock(), 4);
+        game.undo_move().unwrap();
+        assert_eq!(game.halfmove_clock(), 3);
+
+        game.undo_move().unwrap();
+        game.undo_move().unwrap();
+        game.undo_move().unwrap();
+        game.undo_move().unwrap();
+        game.undo_move().unwrap();
+        assert_eq!(game.to_fen(), STARTING_FEN);
+        assert_eq!(game.undo_move(), Err(GameError::NothingToUndo));
+    }
+
+    #[test]
+    fn random_games_terminate() {
+        let mut game = Game::new(Box::new(RandomPlayer), Box::new(RandomPlayer));
+        let result = game.play().unwrap();
+
+        assert_eq!(game.result, Some(result));
+    }
+}
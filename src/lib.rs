@@ -1,6 +1,42 @@
 mod game;
 mod board;
+mod error;
+mod evaluation;
 mod move_;
 mod player;
 mod types;
+mod zobrist;
 mod move_validator;
+mod magic;
+mod perft;
+mod pgn;
+mod protocol;
+#[cfg(test)]
+mod ray_walk;
+mod render;
+mod san;
+mod search;
+mod transposition;
+mod uci;
+mod uci_player;
+mod xboard;
+
+pub use board::{Board, UndoInfo, STARTING_FEN};
+pub use evaluation::{evaluate, EvalTerm, Evaluation};
+pub use error::{FenError, GameError, IllegalMoveReason, NotationError, PgnError};
+pub use game::Game;
+pub use move_::Move;
+pub use move_validator::MoveValidator;
+pub use perft::{divide, perft};
+pub use pgn::{write_pgn, PgnGame, PgnMove, PgnReader};
+pub use player::{CliPlayer, EnginePlayer, Player, PlayerAction, PlayerView, RandomPlayer};
+pub use render::{BoardRenderer, PieceStyle};
+pub use search::{SearchInfo, SearchLimits, Searcher, MATE_SCORE};
+pub use transposition::{Bound, TranspositionTable, TtEntry, DEFAULT_HASH_MB};
+pub use types::{
+    algebraic_to_square, square_to_algebraic, Color, DrawReason, GameResult, GameStatus, PieceType, Termination,
+    TimeControl,
+};
+pub use uci::{UciEngine, ENGINE_AUTHOR, ENGINE_NAME};
+pub use uci_player::UciEnginePlayer;
+pub use xboard::XBoardEngine;
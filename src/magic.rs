@@ -0,0 +1,263 @@
+use std::sync::LazyLock;
+
+// Rook and bishop attacks by magic bitboard lookup. For every square the pieces standing on
+// the squares that can block a slider (its mask) are multiplied by a magic number so that
+// the top bits of the product index that square's slice of a shared attack table. The table
+// is filled on first use by walking the rays for every arrangement of blockers.
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (-1, 1), (1, -1), (-1, -1)];
+
+// Found by trying sparse random numbers, the AND of three splitmix64 outputs, until one
+// mapped every arrangement of blockers without two different attack sets sharing an entry.
+// The search takes too long to repeat on every start.
+#[rustfmt::skip]
+const ROOK_MAGICS: [u64; 64] = [
+    0xA080_0018_2040_0080, 0x0040_0020_0040_1000, 0x0180_3001_6000_8008, 0x0480_0408_0080_1001,
+    0x2A00_0810_8420_4200, 0x0480_0180_1200_3400, 0x0600_0100_8200_0428, 0x4200_0225_0C01_8042,
+    0x0040_8000_4000_2080, 0x0000_4000_2000_500C, 0x2002_0040_2200_1080, 0x0026_0022_0040_0810,
+    0x2000_8080_0800_0400, 0x0022_0002_0088_3104, 0x2C88_8080_0100_0200, 0x1112_0000_8042_0104,
+    0x0100_9080_0040_0020, 0x0080_8080_2000_4000, 0x0008_4100_1020_0300, 0x0014_8080_1000_0801,
+    0x0080_0500_1100_4800, 0x00D1_0100_0208_0400, 0xA080_0400_0A30_0158, 0x1000_1200_0528_8244,
+    0x020C_4000_8024_8002, 0x4020_4112_0022_0082, 0x8028_1000_8020_0881, 0x1210_0011_0009_0020,
+    0x005A_0052_0008_4520, 0x0080_0400_8002_0080, 0x0002_0002_0084_0148, 0x440B_210A_0000_6884,
+    0x0880_4010_2880_0080, 0x2000_8020_0880_4000, 0x2160_0010_4100_2900, 0x2010_2040_0A00_1200,
+    0x8018_0100_0900_1104, 0x2480_8004_0080_0200, 0x0000_0108_0400_0210, 0x0020_0080_4200_3104,
+    0x0000_8020_4000_8000, 0x0010_0020_0040_4000, 0x0001_0010_2001_0041, 0x8840_1000_0901_0022,
+    0x8048_0040_2004_0400, 0x2000_0400_0200_8080, 0x0803_0002_0001_0084, 0x0010_0044_0082_0001,
+    0xA881_4107_2080_0100, 0x0008_208A_0045_0600, 0x0000_8020_0010_0080, 0x0044_08A2_4092_0200,
+    0x6000_8004_0008_0080, 0x0020_0400_0200_8080, 0x8003_000A_0024_5500, 0x0100_8420_8100_4200,
+    0x0000_2018_4082_0102, 0x0011_0020_4000_8019, 0x0011_81C2_0020_501A, 0x1C10_0144_8820_1101,
+    0x0002_0020_0411_0802, 0x0881_0002_0400_0801, 0x2000_8801_4210_0094, 0x0001_5405_0022_C082,
+];
+
+#[rustfmt::skip]
+const BISHOP_MAGICS: [u64; 64] = [
+    0x0002_0214_1804_8103, 0x0023_1001_0210_8001, 0x1622_0081_1200_0818, 0x0610_8912_0100_02D0,
+    0x4002_0210_0020_2400, 0x41C1_0108_4001_2100, 0x0028_8410_02D1_0100, 0x2820_8184_0911_4080,
+    0x0082_2420_4831_2111, 0xA028_6808_2800_4050, 0x0030_1001_4214_2020, 0x8100_0440_4088_0800,
+    0x9004_0404_2220_0240, 0x2400_0111_1840_0422, 0x0030_2044_0220_1008, 0x4280_468A_4C02_2081,
+    0x0540_0410_1081_0140, 0x4030_0008_8280_8400, 0x4010_0001_0408_2045, 0xC004_0488_0410_1401,
+    0x0102_0234_0121_0801, 0x0000_4002_0042_2000, 0x0882_1001_0090_6408, 0x1001_0004_4100_9008,
+    0x40D1_4000_2802_0442, 0x0408_0820_3C10_02AC, 0x1000_5008_1806_8010, 0x2084_0800_2020_2040,
+    0x0001_0101_0410_4000, 0x0008_0200_0040_4200, 0x0048_2900_0A41_4810, 0x2584_1040_8226_0204,
+    0x0828_0444_80D0_E080, 0x0101_4420_0630_0100, 0x4000_8401_1230_0040, 0x0220_A008_0001_0104,
+    0x8010_4900_4204_0040, 0x0000_A200_8044_1001, 0x4290_0101_2040_4C00, 0x8028_0100_4A09_0042,
+    0x0001_0422_2104_4004, 0x4404_10A8_0800_4410, 0x0010_8400_4801_0101, 0x1010_0020_1802_0900,
+    0x0510_2004_A082_2C00, 0x0040_0408_0288_2210, 0x1A10_1400_E080_8C01, 0x3101_0154_0080_0100,
+    0x2002_0801_D808_0000, 0x0009_804C_4220_0000, 0x0001_2824_2228_0004, 0x1040_0000_8404_0021,
+    0x0090_0420_0344_0002, 0x8000_0848_1004_2001, 0x0041_1001_1200_80D0, 0x0820_4805_4100_2910,
+    0xB211_0080_4120_1000, 0x0200_0028_8808_484C, 0x1108_8010_8058_0800, 0x0020_1002_8084_0C40,
+    0x0440_0801_210A_4C02, 0x8004_0485_2014_0110, 0x004C_1004_0800_8408, 0x2350_2022_0428_21A0,
+];
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupied: u64) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct SliderAttacks {
+    rook: [Magic; 64],
+    bishop: [Magic; 64],
+    table: Vec<u64>,
+}
+
+static ATTACKS: LazyLock<SliderAttacks> = LazyLock::new(|| {
+    let mut table = Vec::new();
+    let rook = fill_table(&ROOK_DIRECTIONS, &ROOK_MAGICS, &mut table);
+    let bishop = fill_table(&BISHOP_DIRECTIONS, &BISHOP_MAGICS, &mut table);
+    SliderAttacks { rook, bishop, table }
+});
+
+pub(crate) fn rook_attacks(square: u8, occupied: u64) -> u64 {
+    let attacks = &*ATTACKS;
+    attacks.table[attacks.rook[square as usize].index(occupied)]
+}
+
+pub(crate) fn bishop_attacks(square: u8, occupied: u64) -> u64 {
+    let attacks = &*ATTACKS;
+    attacks.table[attacks.bishop[square as usize].index(occupied)]
+}
+
+// Appends the slice of every square to `table`, filled by walking the rays along `directions`
+fn fill_table(directions: &[(i8, i8); 4], magics: &[u64; 64], table: &mut Vec<u64>) -> [Magic; 64] {
+    let mut entries = [Magic::default(); 64];
+
+    for (square, entry) in entries.iter_mut().enumerate() {
+        let mask = blocker_mask(square as u8, directions);
+        let bits = mask.count_ones();
+        *entry = Magic { mask, magic: magics[square], shift: 64 - bits, offset: table.len() };
+        table.resize(table.len() + (1 << bits), 0);
+
+        // Every subset of the mask, by the carry-rippler trick
+        let mut occupied = 0u64;
+        loop {
+            let attacks = sliding_attacks(square as u8, occupied, directions);
+            let slot = &mut table[entry.index(occupied)];
+            assert!(*slot == 0 || *slot == attacks, "the magic number of square {} collides", square);
+            *slot = attacks;
+
+            occupied = occupied.wrapping_sub(mask) & mask;
+            if occupied == 0 {
+                break;
+            }
+        }
+    }
+
+    entries
+}
+
+// The squares whose occupant can stop a slider: its rays without the edge square at their end
+fn blocker_mask(square: u8, directions: &[(i8, i8); 4]) -> u64 {
+    let mut mask = 0;
+    for &(file_step, rank_step) in directions {
+        let ray = sliding_attacks(square, 0, &[(file_step, rank_step)]);
+        let mut edge = 0;
+        let (mut file, mut rank) = ((square % 8) as i8, (square / 8) as i8);
+        while (0..8).contains(&(file + file_step)) && (0..8).contains(&(rank + rank_step)) {
+            file += file_step;
+            rank += rank_step;
+            edge = 1u64 << (rank * 8 + file);
+        }
+        mask |= ray & !edge;
+    }
+    mask
+}
+
+// The squares a slider attacks along `directions`, up to and including the first piece on each
+fn sliding_attacks(square: u8, occupied: u64, directions: &[(i8, i8)]) -> u64 {
+    let mut attacks = 0;
+    for &(file_step, rank_step) in directions {
+        let (mut file, mut rank) = ((square % 8) as i8, (square / 8) as i8);
+        loop {
+            file += file_step;
+            rank += rank_step;
+            if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+                break;
+            }
+            let bit = 1u64 << (rank * 8 + file);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+        }
+    }
+    attacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::move_validator::MoveValidator;
+    use crate::ray_walk;
+    use crate::types::{algebraic_to_square, Color};
+
+    // splitmix64
+    fn next_random(state: &mut u64) -> u64 {
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn bits(squares: &[&str]) -> u64 {
+        squares.iter().fold(0, |bitboard, name| bitboard | 1u64 << algebraic_to_square(name).unwrap())
+    }
+
+    // The old ray walk shares no code with the tables, unlike sliding_attacks, which fills them
+    #[test]
+    fn lookups_match_the_rays() {
+        let mut random = 7u64;
+        for square in 0..64u8 {
+            for _ in 0..200 {
+                let occupied = next_random(&mut random) & next_random(&mut random);
+                assert_eq!(rook_attacks(square, occupied), ray_walk::rook_attacks(square, occupied));
+                assert_eq!(bishop_attacks(square, occupied), ray_walk::bishop_attacks(square, occupied));
+            }
+        }
+    }
+
+    // Compares every lookup with the old ray walk in the position and in those up to `depth` plies after it
+    fn assert_matches_rays(board: &mut Board, turn: Color, depth: u32, validator: &MoveValidator) {
+        let occupied = board.all_pieces();
+        for square in 0..64u8 {
+            assert_eq!(rook_attacks(square, occupied), ray_walk::rook_attacks(square, occupied));
+            assert_eq!(bishop_attacks(square, occupied), ray_walk::bishop_attacks(square, occupied));
+        }
+
+        if depth > 0 {
+            for move_ in validator.get_legal_moves(board, turn) {
+                let undo = board.make_move(&move_);
+                assert_matches_rays(board, turn.opponent(), depth - 1, validator);
+                board.unmake_move(&move_, &undo);
+            }
+        }
+    }
+
+    #[test]
+    fn lookups_match_the_rays_in_perft_positions() {
+        let validator = MoveValidator::new();
+
+        for (fen, turn) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Color::White),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", Color::White),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", Color::White),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", Color::White),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", Color::White),
+            ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", Color::White),
+        ] {
+            assert_matches_rays(&mut Board::from_fen(fen).unwrap(), turn, 2, &validator);
+        }
+    }
+
+    #[test]
+    fn lookups_match_hand_computed_attacks() {
+        let square = |name| algebraic_to_square(name).unwrap();
+
+        // Corners and edges of an empty board
+        assert_eq!(
+            rook_attacks(square("a1"), 0),
+            bits(&["a2", "a3", "a4", "a5", "a6", "a7", "a8", "b1", "c1", "d1", "e1", "f1", "g1", "h1"])
+        );
+        assert_eq!(
+            rook_attacks(square("h5"), 0),
+            bits(&["h1", "h2", "h3", "h4", "h6", "h7", "h8", "a5", "b5", "c5", "d5", "e5", "f5", "g5"])
+        );
+        assert_eq!(bishop_attacks(square("h8"), 0), bits(&["g7", "f6", "e5", "d4", "c3", "b2", "a1"]));
+        assert_eq!(bishop_attacks(square("a4"), 0), bits(&["b3", "c2", "d1", "b5", "c6", "d7", "e8"]));
+
+        // Blockers are attacked themselves and hide what stands behind them
+        let occupied = bits(&["a3", "c1", "h1", "c3", "e5"]);
+        assert_eq!(rook_attacks(square("a1"), occupied), bits(&["a2", "a3", "b1", "c1"]));
+        assert_eq!(bishop_attacks(square("a1"), occupied), bits(&["b2", "c3"]));
+        assert_eq!(bishop_attacks(square("d4"), occupied), bits(&["c3", "e5", "e3", "f2", "g1", "c5", "b6", "a7"]));
+        assert_eq!(
+            rook_attacks(square("c3"), occupied),
+            bits(&["c1", "c2", "a3", "b3", "d3", "e3", "f3", "g3", "h3", "c4", "c5", "c6", "c7", "c8"])
+        );
+    }
+
+    #[test]
+    fn masks_leave_out_the_edges() {
+        // Rook on a1: b1-g1 and a2-a7
+        assert_eq!(blocker_mask(0, &ROOK_DIRECTIONS), 0x0001_0101_0101_017E);
+        // Bishop on d4: everything on its diagonals but the border squares
+        assert_eq!(blocker_mask(27, &BISHOP_DIRECTIONS).count_ones(), 9);
+
+        // A rook on e4 stops at the first piece in each direction
+        let occupied = (1u64 << 36) | (1u64 << 25) | (1u64 << 30);
+        let expected = [36, 20, 12, 4, 27, 26, 25, 29, 30].iter().fold(0u64, |bitboard, &square| bitboard | 1 << square);
+        assert_eq!(rook_attacks(28, occupied), expected);
+    }
+}
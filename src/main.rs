@@ -0,0 +1,331 @@
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use rust_chess::{
+    perft, BoardRenderer, CliPlayer, Color, EnginePlayer, Game, GameResult, MoveValidator, PieceStyle, Player,
+    RandomPlayer, SearchLimits, Termination, TimeControl, UciEnginePlayer, STARTING_FEN,
+};
+
+const USAGE: &str = "Usage: rust-chess <command> [options]
+
+Commands:
+  human-vs-human     Two humans share the terminal
+  human-vs-engine    Play against the engine
+  engine-vs-engine   Watch two engines play
+  perft              Count the legal moves to each depth and time the move generator
+  help               Show this message
+
+Options:
+  --fen <FEN>          Start from the given position instead of the initial one
+  --color <COLOR>      The human's color against the engine: white (default) or black
+  --white <ENGINE>     Engine playing white: alphabeta (default), random, or uci:<PATH>
+                       for an external UCI engine
+  --black <ENGINE>     Engine playing black, as for --white
+  --depth <PLIES>      How deep the alphabeta engine searches, 4 by default, or perft counts, 5 by default
+  --time <CONTROL>     Time control in seconds with an optional increment, e.g. 300 or 180+2
+  --pgn <PATH>         Write the finished game to a PGN file
+  --unicode            Draw the pieces with Unicode chess symbols
+  --ansi               Color the board and highlight the last move and checks";
+
+const DEFAULT_PERFT_DEPTH: u32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    HumanVsHuman,
+    HumanVsEngine,
+    EngineVsEngine,
+    Perft,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    mode: Mode,
+    fen: String,
+    human_color: Color,
+    white_engine: String,
+    black_engine: String,
+    depth: Option<u32>,
+    time_control: Option<TimeControl>,
+    pgn_path: Option<String>,
+    renderer: BoardRenderer,
+}
+
+// Returns None when only the usage was asked for
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mode = match args.first().map(String::as_str) {
+        Some("human-vs-human") => Mode::HumanVsHuman,
+        Some("human-vs-engine") => Mode::HumanVsEngine,
+        Some("engine-vs-engine") => Mode::EngineVsEngine,
+        Some("perft") => Mode::Perft,
+        Some("help" | "-h" | "--help") | None => return Ok(None),
+        Some(other) => return Err(format!("Unknown command '{}'", other)),
+    };
+
+    let mut options = Options {
+        mode,
+        fen: STARTING_FEN.to_string(),
+        human_color: Color::White,
+        white_engine: "alphabeta".to_string(),
+        black_engine: "alphabeta".to_string(),
+        depth: None,
+        time_control: None,
+        pgn_path: None,
+        renderer: BoardRenderer::new(),
+    };
+
+    let mut rest = args[1..].iter();
+    while let Some(option) = rest.next() {
+        let mut value = || rest.next().cloned().ok_or_else(|| format!("Option {} needs a value", option));
+
+        match option.as_str() {
+            "--fen" => options.fen = value()?,
+            "--color" => {
+                options.human_color = match value()?.as_str() {
+                    "white" | "w" => Color::White,
+                    "black" | "b" => Color::Black,
+                    other => return Err(format!("Color must be white or black, found '{}'", other)),
+                }
+            }
+            "--white" => options.white_engine = value()?,
+            "--black" => options.black_engine = value()?,
+            "--depth" => {
+                let depth = value()?;
+                options.depth = Some(
+                    depth.parse().ok().filter(|&depth| depth > 0).ok_or_else(|| format!("Invalid depth '{}'", depth))?,
+                );
+            }
+            "--time" => options.time_control = Some(parse_time_control(&value()?)?),
+            "--pgn" => options.pgn_path = Some(value()?),
+            "--unicode" => options.renderer = options.renderer.style(PieceStyle::Unicode),
+            "--ansi" => options.renderer = options.renderer.ansi_colors(true),
+            "-h" | "--help" => return Ok(None),
+            other => return Err(format!("Unknown option '{}'", other)),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+// Seconds with an optional increment in seconds, e.g. "300" or "180+2"
+fn parse_time_control(text: &str) -> Result<TimeControl, String> {
+    let (initial, increment) = text.split_once('+').unwrap_or((text, "0"));
+    let seconds = |field: &str| {
+        field
+            .parse::<f64>()
+            .ok()
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .ok_or_else(|| format!("Invalid time control '{}', expected e.g. 300 or 180+2", text))
+    };
+
+    Ok(TimeControl::new(seconds(initial)?, seconds(increment)?))
+}
+
+fn engine(name: &str, options: &Options) -> Result<Box<dyn Player>, String> {
+    match name {
+        "alphabeta" => Ok(Box::new(match options.depth {
+            Some(depth) => EnginePlayer::with_limits(SearchLimits::new().depth(depth)),
+            None => EnginePlayer::new(),
+        })),
+        "random" => Ok(Box::new(RandomPlayer)),
+        other => match other.strip_prefix("uci:") {
+            Some(path) => {
+                let engine = UciEnginePlayer::new(path).map_err(|error| format!("Cannot start '{}': {}", path, error))?;
+                Ok(Box::new(match options.depth {
+                    Some(depth) => engine.limits(SearchLimits::new().depth(depth)),
+                    None => engine,
+                }))
+            }
+            None => Err(format!("Unknown engine '{}'", other)),
+        },
+    }
+}
+
+// The white and black player
+type Players = (Box<dyn Player>, Box<dyn Player>);
+
+fn players(options: &Options) -> Result<Players, String> {
+    let human = || -> Box<dyn Player> { Box::new(CliPlayer::new().renderer(options.renderer)) };
+
+    Ok(match (options.mode, options.human_color) {
+        (Mode::HumanVsHuman, _) => (human(), human()),
+        (Mode::HumanVsEngine, Color::White) => (human(), engine(&options.black_engine, options)?),
+        (Mode::HumanVsEngine, Color::Black) => (engine(&options.white_engine, options)?, human()),
+        (Mode::EngineVsEngine, _) => (engine(&options.white_engine, options)?, engine(&options.black_engine, options)?),
+        (Mode::Perft, _) => unreachable!("perft is run without players"),
+    })
+}
+
+fn player_name(options: &Options, color: Color) -> String {
+    let is_human = match options.mode {
+        Mode::HumanVsHuman => true,
+        Mode::HumanVsEngine => color == options.human_color,
+        Mode::EngineVsEngine | Mode::Perft => false,
+    };
+
+    let engine = match color {
+        Color::White => &options.white_engine,
+        Color::Black => &options.black_engine,
+    };
+    match (is_human, engine.strip_prefix("uci:")) {
+        (true, _) => "Human".to_string(),
+        (false, Some(path)) => path.to_string(),
+        (false, None) => format!("rust-chess ({})", engine),
+    }
+}
+
+// Perft from depth 1 up, with the speed of each count
+fn run_perft(options: &Options) -> Result<(), String> {
+    // Game::from_fen reads the side to move along with the position; the players never move
+    let game = Game::from_fen(&options.fen, Box::new(RandomPlayer), Box::new(RandomPlayer))
+        .map_err(|error| error.to_string())?;
+    let (mut board, turn) = (game.board().clone(), game.turn());
+    let move_validator = MoveValidator::new();
+
+    for depth in 1..=options.depth.unwrap_or(DEFAULT_PERFT_DEPTH) {
+        let started = Instant::now();
+        let nodes = perft(&move_validator, &mut board, turn, depth);
+        let elapsed = started.elapsed();
+        let nodes_per_second = nodes as f64 / elapsed.as_secs_f64().max(1e-9);
+        println!(
+            "perft({}) = {:>12} in {:>8.3}s, {:>6.2}M nodes/s",
+            depth,
+            nodes,
+            elapsed.as_secs_f64(),
+            nodes_per_second / 1e6
+        );
+    }
+
+    Ok(())
+}
+
+// E.g. "Game over: 1-0, White wins by checkmate"
+fn game_over_message(result: GameResult, termination: Option<Termination>) -> String {
+    match termination {
+        Some(termination) => format!("Game over: {} by {}", result, termination),
+        None => format!("Game over: {}", result),
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    if options.mode == Mode::Perft {
+        return run_perft(options);
+    }
+
+    let (white, black) = players(options)?;
+    let mut game = Game::from_fen(&options.fen, white, black).map_err(|error| error.to_string())?;
+    if let Some(time_control) = options.time_control {
+        game.set_time_control(time_control);
+    }
+
+    while !game.is_over() {
+        let board = game.board().clone();
+        let plies = game.move_history().len();
+        game.step().map_err(|error| error.to_string())?;
+
+        // Engines move silently, so announce their moves; takebacks shorten the history
+        if options.mode != Mode::HumanVsHuman && game.move_history().len() == plies + 1 {
+            let move_ = game.move_history()[plies];
+            let color = game.turn().opponent();
+            println!("{} plays {}", color, move_.to_san(&board));
+        }
+    }
+
+    let result = game.get_game_result().expect("the game is over");
+    println!("{}", game_over_message(result, game.get_termination()));
+
+    if let Some(path) = &options.pgn_path {
+        let white = player_name(options, Color::White);
+        let black = player_name(options, Color::Black);
+        let pgn = game.to_pgn(&[("Event", "rust-chess game"), ("White", &white), ("Black", &black)]);
+        fs::write(path, pgn).map_err(|error| format!("Cannot write PGN to '{}': {}", path, error))?;
+        println!("Game saved to {}", path);
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let options = match parse_args(&args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_chess::DrawReason;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse_args(&args("human-vs-engine --color black --white random --time 180+2 --pgn out.pgn --unicode"))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(options.mode, Mode::HumanVsEngine);
+        assert_eq!(options.human_color, Color::Black);
+        assert_eq!(options.fen, STARTING_FEN);
+        assert_eq!(options.time_control, Some(TimeControl::new(Duration::from_secs(180), Duration::from_secs(2))));
+        assert_eq!(options.pgn_path.as_deref(), Some("out.pgn"));
+        assert_eq!(options.renderer, BoardRenderer::new().style(PieceStyle::Unicode));
+
+        let fen = ["engine-vs-engine", "--fen", "4k3/8/8/8/8/8/8/4K2R w K - 0 1"].map(str::to_string);
+        assert_eq!(parse_args(&fen).unwrap().unwrap().fen, fen[2]);
+
+        let options = parse_args(&args("perft --depth 3")).unwrap().unwrap();
+        assert_eq!((options.mode, options.depth), (Mode::Perft, Some(3)));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(parse_args(&args("")), Ok(None));
+        assert!(parse_args(&args("chess")).is_err());
+        assert!(parse_args(&args("human-vs-human --color")).is_err());
+        assert!(parse_args(&args("human-vs-engine --color red")).is_err());
+        assert!(parse_args(&args("engine-vs-engine --time fast")).is_err());
+        assert!(parse_args(&args("engine-vs-engine --time 1e300")).is_err());
+        assert!(parse_args(&args("engine-vs-engine --time 60+-1")).is_err());
+        assert!(parse_args(&args("engine-vs-engine --depth 0")).is_err());
+        assert!(parse_args(&args("engine-vs-engine --plies 3")).is_err());
+    }
+
+    #[test]
+    fn game_over_messages_are_plain_words() {
+        assert_eq!(
+            game_over_message(GameResult::WhiteWin, Some(Termination::Checkmate)),
+            "Game over: 1-0, White wins by checkmate"
+        );
+        assert_eq!(
+            game_over_message(GameResult::BlackWin, Some(Termination::Timeout)),
+            "Game over: 0-1, Black wins by time forfeit"
+        );
+        assert_eq!(
+            game_over_message(GameResult::Draw, Some(Termination::Draw(DrawReason::FiftyMoveRule))),
+            "Game over: 1/2-1/2, draw by the fifty-move rule"
+        );
+        assert_eq!(game_over_message(GameResult::Draw, None), "Game over: 1/2-1/2, draw");
+    }
+}
@@ -1,6 +1,12 @@
-use crate::types::PieceType;
+use std::fmt;
 
-pub(crate) struct Move {
+use crate::board::Board;
+use crate::error::NotationError;
+use crate::move_validator::MoveValidator;
+use crate::types::{algebraic_to_square, square_to_algebraic, Color, PieceType};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Move {
     from: u8,
     to: u8,
     promotion: Option<PieceType>,
@@ -8,7 +14,97 @@ pub(crate) struct Move {
 }
 
 impl Move {
-    pub(crate) fn new(from: u8, to: u8, promotion: Option<PieceType>, is_castling: bool) -> Self {
+    pub fn new(from: u8, to: u8, promotion: Option<PieceType>, is_castling: bool) -> Self {
         Self { from, to, promotion, is_castling }
     }
-}
\ No newline at end of file
+
+    // Resolves a UCI long algebraic move (e.g. "e2e4", "e7e8q", "e1g1") to the
+    // matching legal move for the given side
+    pub fn from_uci(uci: &str, board: &Board, color: Color) -> Result<Self, NotationError> {
+        let uci = uci.trim();
+
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(NotationError::Malformed { notation: uci.to_string(), reason: "expected e.g. 'e2e4' or 'e7e8q'" });
+        }
+
+        let from = algebraic_to_square(&uci[0..2])
+            .ok_or_else(|| NotationError::Malformed { notation: uci.to_string(), reason: "invalid source square" })?;
+        let to = algebraic_to_square(&uci[2..4])
+            .ok_or_else(|| NotationError::Malformed { notation: uci.to_string(), reason: "invalid destination square" })?;
+
+        let promotion = match uci[4..].chars().next() {
+            Some(c) => match PieceType::from_char(c) {
+                Some(piece_type @ (PieceType::Knight | PieceType::Bishop | PieceType::Rook | PieceType::Queen)) => {
+                    Some(piece_type)
+                }
+                _ => {
+                    return Err(NotationError::Malformed { notation: uci.to_string(), reason: "invalid promotion piece" });
+                }
+            },
+            None => None,
+        };
+
+        MoveValidator::new()
+            .get_legal_moves(board, color)
+            .into_iter()
+            .find(|move_| move_.from == from && move_.to == to && move_.promotion == promotion)
+            .ok_or_else(|| NotationError::Illegal { notation: uci.to_string(), color })
+    }
+
+    pub fn from(&self) -> u8 {
+        self.from
+    }
+
+    pub fn to(&self) -> u8 {
+        self.to
+    }
+
+    pub fn promotion(&self) -> Option<PieceType> {
+        self.promotion
+    }
+
+    pub fn is_castling(&self) -> bool {
+        self.is_castling
+    }
+}
+
+// UCI long algebraic notation, e.g. "e2e4", "e7e8q" or "e1g1" for castling
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", square_to_algebraic(self.from), square_to_algebraic(self.to))?;
+
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.to_char())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uci_round_trip() {
+        let board = Board::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+
+        for uci in ["e1g1", "e1c1", "e5d6", "b7a8q", "b7b8n", "a1a8"] {
+            let move_ = Move::from_uci(uci, &board, Color::White).unwrap();
+            assert_eq!(move_.to_string(), uci);
+        }
+
+        assert!(Move::from_uci("e1g1", &board, Color::White).unwrap().is_castling());
+    }
+
+    #[test]
+    fn uci_rejects_bad_input() {
+        let board = Board::new();
+
+        assert!(Move::from_uci("e2e5", &board, Color::White).is_err());
+        assert!(Move::from_uci("e7e5", &board, Color::White).is_err());
+        assert!(Move::from_uci("e2e4x", &board, Color::White).is_err());
+        assert!(Move::from_uci("i2e4", &board, Color::White).is_err());
+        assert!(Move::from_uci("e2", &board, Color::White).is_err());
+    }
+}
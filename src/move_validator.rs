@@ -1,102 +1,77 @@
 use crate::board::Board;
+use crate::error::IllegalMoveReason;
+use crate::magic;
 use crate::move_::Move;
+#[cfg(test)]
+use crate::ray_walk;
 use crate::types::{Color, PieceType};
 
-const KING_MOVES: [u64; 64] = {
-    let mut moves = [0u64; 64];
-    for sq in 0..64 {
+// King attacks only (no castling)
+pub(crate) const KING_ATTACKS: [u64; 64] = {
+    let mut attacks = [0u64; 64];
+    let mut sq = 0;
+    while sq < 64 {
         let bit = 1u64 << sq;
-        moves[sq] = (bit << 8) |                          // Move up
+        attacks[sq] = (bit << 8) |                        // Move up
             (bit >> 8) |                                  // Move down
             (bit << 1 & !0x0101010101010101) |            // Move right (prevent A-file to H-file wrap)
             (bit >> 1 & !0x8080808080808080) |            // Move left (prevent H-file to A-file wrap)
-            (bit << 9 & !0x8080808080808080) |            // Move up-right
-            (bit >> 9 & !0x0101010101010101) |            // Move down-left
-            (bit << 7 & !0x0101010101010101) |            // Move up-left
-            (bit >> 7 & !0x8080808080808080);             // Move down-right
-
-        // Add castling moves to the starting positions
-        if sq == 4 {  // White King starting position (e1)
-            // Add kingside castle (g1)
-            moves[sq] |= 1u64 << 6;
-            // Add queenside castle (c1)
-            moves[sq] |= 1u64 << 2;
-        }
-
-        if sq == 60 {  // Black King starting position (e8)
-            // Add kingside castle (g8)
-            moves[sq] |= 1u64 << 62;
-            // Add queenside castle (c8)
-            moves[sq] |= 1u64 << 58;
-        }
+            (bit << 9 & !0x0101010101010101) |            // Move up-right
+            (bit >> 9 & !0x8080808080808080) |            // Move down-left
+            (bit << 7 & !0x8080808080808080) |            // Move up-left
+            (bit >> 7 & !0x0101010101010101);             // Move down-right
+        sq += 1;
     }
+    attacks
+};
+
+const KING_MOVES: [u64; 64] = {
+    let mut moves = KING_ATTACKS;
+
+    // Add castling moves to the starting positions
+    // White King starting position (e1): kingside castle (g1) and queenside castle (c1)
+    moves[4] |= (1u64 << 6) | (1u64 << 2);
+
+    // Black King starting position (e8): kingside castle (g8) and queenside castle (c8)
+    moves[60] |= (1u64 << 62) | (1u64 << 58);
+
     moves
 };
 
-const KNIGHT_MOVES: [u64; 64] = {
+pub(crate) const KNIGHT_MOVES: [u64; 64] = {
     let mut moves = [0u64; 64];
-    for sq in 0..64 {
+    let mut sq = 0;
+    while sq < 64 {
         let bit = 1u64 << sq;
 
         // Knight moves in all 8 directions:
         // Up 2, right 1
-        moves[sq] |= (bit << 17) & !0x01010101010101;
+        moves[sq] |= (bit << 17) & !0x0101010101010101;
         // Up 2, left 1
-        moves[sq] |= (bit << 15) & !0x80808080808080;
+        moves[sq] |= (bit << 15) & !0x8080808080808080;
         // Up 1, right 2
         moves[sq] |= (bit << 10) & !0x0303030303030303;
         // Up 1, left 2
         moves[sq] |= (bit << 6) & !0xC0C0C0C0C0C0C0C0;
 
         // Down 2, right 1
-        moves[sq] |= (bit >> 15) & !0x01010101010101;
+        moves[sq] |= (bit >> 15) & !0x0101010101010101;
         // Down 2, left 1
-        moves[sq] |= (bit >> 17) & !0x80808080808080;
+        moves[sq] |= (bit >> 17) & !0x8080808080808080;
         // Down 1, right 2
         moves[sq] |= (bit >> 6) & !0x0303030303030303;
         // Down 1, left 2
         moves[sq] |= (bit >> 10) & !0xC0C0C0C0C0C0C0C0;
-    }
-    moves
-};
-
-// Forward push moves only (no captures)
-const WHITE_PAWN_PUSHES: [u64; 64] = {
-    let mut moves = [0u64; 64];
-    for sq in 0..64 {
-        let bit = 1u64 << sq;
-
-        // Single push (one square forward)
-        moves[sq] |= bit << 8;
-
-        // Double push (only from rank 2)
-        if sq >= 8 && sq < 16 {
-            moves[sq] |= bit << 16;
-        }
-    }
-    moves
-};
-
-const BLACK_PAWN_PUSHES: [u64; 64] = {
-    let mut moves = [0u64; 64];
-    for sq in 0..64 {
-        let bit = 1u64 << sq;
-
-        // Single push (one square forward)
-        moves[sq] |= bit >> 8;
-
-        // Double push (only from rank 7)
-        if sq >= 48 && sq < 56 {
-            moves[sq] |= bit >> 16;
-        }
+        sq += 1;
     }
     moves
 };
 
 // Diagonal attack moves only
-const WHITE_PAWN_ATTACKS: [u64; 64] = {
+pub(crate) const WHITE_PAWN_ATTACKS: [u64; 64] = {
     let mut attacks = [0u64; 64];
-    for sq in 0..64 {
+    let mut sq = 0;
+    while sq < 64 {
         let bit = 1u64 << sq;
         if sq % 8 != 0 {  // Not on A-file
             attacks[sq] |= bit << 7;  // Attack left
@@ -104,13 +79,15 @@ const WHITE_PAWN_ATTACKS: [u64; 64] = {
         if sq % 8 != 7 {  // Not on H-file
             attacks[sq] |= bit << 9;  // Attack right
         }
+        sq += 1;
     }
     attacks
 };
 
-const BLACK_PAWN_ATTACKS: [u64; 64] = {
+pub(crate) const BLACK_PAWN_ATTACKS: [u64; 64] = {
     let mut attacks = [0u64; 64];
-    for sq in 0..64 {
+    let mut sq = 0;
+    while sq < 64 {
         let bit = 1u64 << sq;
         if sq % 8 != 7 {  // Not on H-file
             attacks[sq] |= bit >> 7;  // Attack left
@@ -118,178 +95,119 @@ const BLACK_PAWN_ATTACKS: [u64; 64] = {
         if sq % 8 != 0 {  // Not on A-file
             attacks[sq] |= bit >> 9;  // Attack right
         }
+        sq += 1;
     }
     attacks
 };
 
-// North rays (upward movement)
-const NORTH_RAYS: [u64; 64] = {
-    let mut rays = [0u64; 64];
-    for sq in 0..64 {
-        let mut ray = 0u64;
-        let mut bit = 1u64 << sq;
-        for _ in 0..7 {
-            bit = bit << 8; // Move up one rank
-            ray |= bit;
-        }
-        rays[sq] = ray;
-    }
-    rays
-};
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MoveValidator {
 
-// South rays (downward movement)
-const SOUTH_RAYS: [u64; 64] = {
-    let mut rays = [0u64; 64];
-    for sq in 0..64 {
-        let mut ray = 0u64;
-        let mut bit = 1u64 << sq;
-        for _ in 0..7 {
-            bit = bit >> 8; // Move down one rank
-            ray |= bit;
-        }
-        rays[sq] = ray;
-    }
-    rays
-};
+}
+
+impl MoveValidator {
+
+    pub fn new() -> Self {
+        Self {
 
-// East rays (rightward movement)
-const EAST_RAYS: [u64; 64] = {
-    let mut rays = [0u64; 64];
-    for sq in 0..64 {
-        let mut ray = 0u64;
-        let mut bit = 1u64 << sq;
-        let file = sq % 8;
-        for _ in 0..(7 - file) { // Stop at the H file
-            bit = bit << 1; // Move right one file
-            ray |= bit;
         }
-        rays[sq] = ray;
     }
-    rays
-};
 
-// West rays (leftward movement)
-const WEST_RAYS: [u64; 64] = {
-    let mut rays = [0u64; 64];
-    for sq in 0..64 {
-        let mut ray = 0u64;
-        let mut bit = 1u64 << sq;
-        let file = sq % 8;
-        for _ in 0..file { // Stop at the A file
-            bit = bit >> 1; // Move left one file
-            ray |= bit;
-        }
-        rays[sq] = ray;
+    pub fn get_legal_moves(&self, board: &Board, color: Color) -> Vec<Move> {
+        self.get_pseudo_legal_moves(board, color)
+            .into_iter()
+            .filter(|move_| self.check_king_safety(board, move_, color).is_ok())
+            .collect()
     }
-    rays
-};
 
-// Northeast rays (up-right movement)
-const NORTH_EAST_RAYS: [u64; 64] = {
-    let mut rays = [0u64; 64];
-    for sq in 0..64 {
-        let mut ray = 0u64;
-        let mut bit = 1u64 << sq;
-        let file = sq % 8;
-        let mut rank_file = (sq / 8, file);
-
-        while rank_file.0 < 7 && rank_file.1 < 7 {
-            bit <<= 9; // Move up one rank and one file right
-            ray |= bit;
-            rank_file = (rank_file.0 + 1, rank_file.1 + 1);
-        }
-        rays[sq] = ray;
+    pub fn is_move_legal(&self, board: &Board, move_: &Move) -> bool {
+        // The side making the move is the owner of the piece on the source square
+        let Some((color, _)) = board.piece_at(move_.from()) else {
+            return false;
+        };
+
+        self.validate_move(board, move_, color).is_ok()
     }
-    rays
-};
 
-// Northwest rays (up-left movement)
-const NORTH_WEST_RAYS: [u64; 64] = {
-    let mut rays = [0u64; 64];
-    for sq in 0..64 {
-        let mut ray = 0u64;
-        let mut bit = 1u64 << sq;
-        let file = sq % 8;
-        let mut rank_file = (sq / 8, file);
-
-        while rank_file.0 < 7 && rank_file.1 > 0 {
-            bit <<= 7; // Move up one rank and one file left
-            ray |= bit;
-            rank_file = (rank_file.0 + 1, rank_file.1 - 1);
+    // Checks that the given side may play the move, and if not, why
+    pub fn validate_move(&self, board: &Board, move_: &Move, color: Color) -> Result<(), IllegalMoveReason> {
+        match board.piece_at(move_.from()) {
+            None => return Err(IllegalMoveReason::NoPieceOnSource),
+            Some((owner, _)) if owner != color => return Err(IllegalMoveReason::WrongSideToMove),
+            Some(_) => {}
         }
-        rays[sq] = ray;
-    }
-    rays
-};
 
-// Southeast rays (down-right movement)
-const SOUTH_EAST_RAYS: [u64; 64] = {
-    let mut rays = [0u64; 64];
-    for sq in 0..64 {
-        let mut ray = 0u64;
-        let mut bit = 1u64 << sq;
-        let file = sq % 8;
-        let mut rank_file = (sq / 8, file);
-
-        while rank_file.0 > 0 && rank_file.1 < 7 {
-            bit >>= 7; // Move down one rank and one file right
-            ray |= bit;
-            rank_file = (rank_file.0 - 1, rank_file.1 + 1);
+        if !self.get_pseudo_legal_moves(board, color).contains(move_) {
+            return Err(IllegalMoveReason::InvalidMovement);
         }
-        rays[sq] = ray;
+
+        self.check_king_safety(board, move_, color)
     }
-    rays
-};
 
-// Southwest rays (down-left movement)
-const SOUTH_WEST_RAYS: [u64; 64] = {
-    let mut rays = [0u64; 64];
-    for sq in 0..64 {
-        let mut ray = 0u64;
-        let mut bit = 1u64 << sq;
-        let file = sq % 8;
-        let mut rank_file = (sq / 8, file);
-
-        while rank_file.0 > 0 && rank_file.1 > 0 {
-            bit >>= 9; // Move down one rank and one file left
-            ray |= bit;
-            rank_file = (rank_file.0 - 1, rank_file.1 - 1);
+    // A pseudo-legal move is legal if it does not leave the mover's king attacked.
+    // Playing it on a scratch board also covers pins and discovered checks from en passant captures.
+    fn check_king_safety(&self, board: &Board, move_: &Move, color: Color) -> Result<(), IllegalMoveReason> {
+        if move_.is_castling() && !self.is_castling_legal(board, move_, color) {
+            return Err(IllegalMoveReason::CastlingNotAllowed);
         }
-        rays[sq] = ray;
-    }
-    rays
-};
 
+        let mut scratch = board.clone();
+        scratch.make_move(move_);
 
+        if scratch.in_check(color) {
+            return Err(IllegalMoveReason::LeavesKingInCheck);
+        }
 
-pub(crate) struct MoveValidator {
+        Ok(())
+    }
 
-}
+    // Castling needs the right, the rook in its corner and an empty path, and the king may not
+    // castle out of or through check. Landing in check is caught by the scratch board test.
+    fn is_castling_legal(&self, board: &Board, move_: &Move, color: Color) -> bool {
+        let (king_square, kingside) = match (color, move_.to()) {
+            (Color::White, 6) => (4u8, true),
+            (Color::White, 2) => (4u8, false),
+            (Color::Black, 62) => (60u8, true),
+            (Color::Black, 58) => (60u8, false),
+            _ => return false,
+        };
 
-impl MoveValidator {
+        let (has_right, rook_square, path, passing_square) = if kingside {
+            (board.can_castle_kingside(color), king_square + 3, 0b11u64 << (king_square + 1), king_square + 1)
+        } else {
+            (board.can_castle_queenside(color), king_square - 4, 0b111u64 << (king_square - 3), king_square - 1)
+        };
 
-    pub(crate) fn new() -> Self {
-        Self {
+        let opponent = color.opponent();
 
-        }
+        move_.from() == king_square
+            && has_right
+            && board.pieces(color, PieceType::Rook) & (1u64 << rook_square) != 0
+            && board.all_pieces() & path == 0
+            && !board.is_square_attacked(king_square, opponent)
+            && !board.is_square_attacked(passing_square, opponent)
     }
 
-    pub(crate) fn get_legal_moves(&self, board: &Board, color: Color) -> Vec<Move> {
-        self.get_pseudo_legal_moves(board, color)
-            .iter()
-            .filter(|move_| self.is_move_legal(board, move_))
-            .collect()
+    // Squares attacked by a rook (or the orthogonal moves of a queen) on the given square
+    pub(crate) fn rook_attacks(square: u8, all_pieces: u64) -> u64 {
+        #[cfg(test)]
+        if ray_walk::in_use() {
+            return ray_walk::rook_attacks(square, all_pieces);
+        }
+        magic::rook_attacks(square, all_pieces)
     }
 
-    pub(crate) fn is_move_legal(&self, board: &Board, move_: &Move) -> bool {
-        false
+    // Squares attacked by a bishop (or the diagonal moves of a queen) on the given square
+    pub(crate) fn bishop_attacks(square: u8, all_pieces: u64) -> u64 {
+        #[cfg(test)]
+        if ray_walk::in_use() {
+            return ray_walk::bishop_attacks(square, all_pieces);
+        }
+        magic::bishop_attacks(square, all_pieces)
     }
 
     fn get_pseudo_legal_king_moves(&self, board: &Board, color: Color) -> Vec<Move> {
-        let bitboard = match color {
-            Color::White => { board.white_king }
-            Color::Black => { board.black_king }
-        };
+        let bitboard = board.pieces(color, PieceType::King);
 
         // Get the bitboard of all pieces of the same color
         let own_pieces = match color {
@@ -297,6 +215,10 @@ impl MoveValidator {
             Color::Black => board.black_pieces(),
         };
 
+        if bitboard == 0 {
+            return Vec::new();
+        }
+
         let king_square = bitboard.trailing_zeros() as u8;
 
         // Filter out moves that would capture own pieces
@@ -307,10 +229,7 @@ impl MoveValidator {
             .iter()
             .map(|sq| {
                 // Determine if it's a castling move
-                let is_castling = match (king_square, *sq) {
-                    (4, 2) | (4, 6) | (60, 58) | (60, 62) => true,
-                    _ => false
-                };
+                let is_castling = matches!((king_square, *sq), (4, 2) | (4, 6) | (60, 58) | (60, 62));
 
                 Move::new(king_square, *sq, None, is_castling)
             })
@@ -319,10 +238,7 @@ impl MoveValidator {
 
     fn get_pseudo_legal_knight_moves(&self, board: &Board, color: Color) -> Vec<Move> {
         // Get the knight bitboard for the current color
-        let knights_bitboard = match color {
-            Color::White => board.white_knights,
-            Color::Black => board.black_knights,
-        };
+        let knights_bitboard = board.pieces(color, PieceType::Knight);
 
         // Get the bitboard of all pieces of the same color
         let own_pieces = match color {
@@ -342,7 +258,7 @@ impl MoveValidator {
             remaining_knights &= !(1u64 << knight_pos);
 
             // Get all possible knight moves from this position
-            let knight_moves_bitboard = KNIGHT_MOVES[knight_pos];
+            let knight_moves_bitboard = KNIGHT_MOVES[knight_pos as usize];
 
             // Filter out moves that would capture own pieces
             let valid_moves_bitboard = knight_moves_bitboard & !own_pieces;
@@ -361,10 +277,7 @@ impl MoveValidator {
 
     fn get_pseudo_legal_pawn_moves(&self, board: &Board, color: Color) -> Vec<Move> {
         // Get pawns for the current color
-        let pawns_bitboard = match color {
-            Color::White => board.white_pawns,
-            Color::Black => board.black_pawns,
-        };
+        let pawns_bitboard = board.pieces(color, PieceType::Pawn);
 
         // Get the bitboard of all pieces
         let all_pieces = board.all_pieces();
@@ -390,12 +303,6 @@ impl MoveValidator {
             let square = remaining_pawns.trailing_zeros() as u8;
             remaining_pawns &= !(1u64 << square);
 
-            // Get potential push moves for this pawn
-            let potential_pushes = match color {
-                Color::White => WHITE_PAWN_PUSHES[square as usize],
-                Color::Black => BLACK_PAWN_PUSHES[square as usize],
-            };
-
             // Get potential attack moves for this pawn
             let potential_attacks = match color {
                 Color::White => WHITE_PAWN_ATTACKS[square as usize],
@@ -423,8 +330,8 @@ impl MoveValidator {
 
                 // Check if pawn is on starting rank
                 let is_on_starting_rank = match color {
-                    Color::White => square >= 8 && square < 16, // 2nd rank
-                    Color::Black => square >= 48 && square < 56, // 7th rank
+                    Color::White => (8..16).contains(&square), // 2nd rank
+                    Color::Black => (48..56).contains(&square), // 7th rank
                 };
 
                 if is_on_starting_rank && (double_push & empty_squares) != 0 {
@@ -436,11 +343,17 @@ impl MoveValidator {
             valid_moves |= potential_attacks & opponent_pieces;
 
             // Process en passant captures
-            if let Some(ep_square) = board.en_passant_square {
+            if let Some(ep_square) = board.en_passant_square() {
                 let ep_bitboard = 1u64 << ep_square;
 
+                // The en passant square must lie behind an opponent pawn (6th rank for white, 3rd for black)
+                let ep_rank = match color {
+                    Color::White => 0x0000FF0000000000,
+                    Color::Black => 0x0000000000FF0000,
+                };
+
                 // Check if this pawn can capture en passant
-                if (potential_attacks & ep_bitboard) != 0 {
+                if (potential_attacks & ep_bitboard & ep_rank) != 0 {
                     valid_moves |= ep_bitboard;
                 }
             }
@@ -452,9 +365,6 @@ impl MoveValidator {
                 // Check if this is a promotion move
                 let is_promotion = (1u64 << dest) & promotion_rank != 0;
 
-                // Check if this is an en passant capture
-                let is_en_passant = board.en_passant_square.map_or(false, |ep| ep == dest);
-
                 if is_promotion {
                     // Generate separate moves for each promotion piece type
                     for promotion_piece in [
@@ -463,11 +373,11 @@ impl MoveValidator {
                         Some(PieceType::Bishop),
                         Some(PieceType::Knight),
                     ] {
-                        moves.push(Move::new(square, dest, promotion_piece, is_en_passant));
+                        moves.push(Move::new(square, dest, promotion_piece, false));
                     }
                 } else {
                     // Regular move or en passant
-                    moves.push(Move::new(square, dest, None, is_en_passant));
+                    moves.push(Move::new(square, dest, None, false));
                 }
             }
         }
@@ -476,148 +386,45 @@ impl MoveValidator {
     }
 
     fn get_pseudo_legal_rook_moves(&self, board: &Board, color: Color) -> Vec<Move> {
-        let mut moves = Vec::new();
-
-        // Get the appropriate rook bitboard and enemy pieces based on color
-        let (mut rooks, enemy_pieces) = match color {
-            Color::White => (board.white_rooks, board.black_pieces()),
-            Color::Black => (board.black_rooks, board.white_pieces()),
-        };
-
-        let all_pieces = board.all_pieces();
-
-        // Iterate through each rook position
-        while rooks != 0 {
-            let from = rooks.trailing_zeros() as u8;
-            rooks &= rooks - 1; // Clear the least significant bit
-
-            // Generate moves for each ray direction (north, south, east, west)
-            for &ray in &[
-                NORTH_RAYS[from as usize],
-                SOUTH_RAYS[from as usize],
-                EAST_RAYS[from as usize],
-                WEST_RAYS[from as usize]
-            ] {
-                self.add_ray_moves(ray, all_pieces, enemy_pieces, from, &mut moves);
-            }
-        }
-
-        moves
+        self.get_pseudo_legal_slider_moves(board, color, PieceType::Rook, Self::rook_attacks)
     }
 
     fn get_pseudo_legal_bishop_moves(&self, board: &Board, color: Color) -> Vec<Move> {
-        let mut moves = Vec::new();
-
-        // Get the appropriate bishop bitboard and enemy pieces based on color
-        let (mut bishops, enemy_pieces) = match color {
-            Color::White => (board.white_bishops, board.black_pieces()),
-            Color::Black => (board.black_bishops, board.white_pieces()),
-        };
-
-        let all_pieces = board.all_pieces();
-
-        // Iterate through each bishop position
-        while bishops != 0 {
-            let from = bishops.trailing_zeros() as u8;
-            bishops &= bishops - 1; // Clear the least significant bit
-
-            // Generate moves for each diagonal ray direction (NE, NW, SE, SW)
-            for &ray in &[
-                NORTH_EAST_RAYS[from as usize],
-                NORTH_WEST_RAYS[from as usize],
-                SOUTH_EAST_RAYS[from as usize],
-                SOUTH_WEST_RAYS[from as usize],
-            ] {
-                self.add_ray_moves(ray, all_pieces, enemy_pieces, from, &mut moves);
-            }
-        }
-
-        moves
+        self.get_pseudo_legal_slider_moves(board, color, PieceType::Bishop, Self::bishop_attacks)
     }
 
-    // Helper method to add moves along a ray
-    fn add_ray_moves(&self, ray: u64, all_pieces: u64, enemy_pieces: u64, from: u8, moves: &mut Vec<Move>) {
-        // Find blockers along this ray
-        let blockers = ray & all_pieces;
-
-        if blockers == 0 {
-            // No blockers - add all squares along the ray as quiet moves
-            self.add_moves_to_empty_squares(ray, from, moves);
-            return;
-        }
-
-        // Find the first blocker in the ray
-        let first_blocker_index = self.find_first_blocker_index(ray, blockers);
-        if first_blocker_index >= 64 {
-            return; // No valid blocker found (shouldn't happen if blockers != 0)
-        }
-
-        let first_blocker = 1u64 << first_blocker_index;
-
-        // Add moves to all squares before the first blocker
-        let squares_before_blocker = 0u64.wrapping_sub(first_blocker);
-        let quiet_moves = ray & !(first_blocker | (ray & squares_before_blocker));
-
-        self.add_moves_to_empty_squares(quiet_moves, from, moves);
-
-        // If the first blocker is an enemy piece, add a capture move
-        if (first_blocker & enemy_pieces) != 0 {
-            moves.push(Move::new(from, first_blocker_index as u8, None, false));
-        }
+    fn get_pseudo_legal_queen_moves(&self, board: &Board, color: Color) -> Vec<Move> {
+        // Queen combines rook and bishop moves
+        self.get_pseudo_legal_slider_moves(board, color, PieceType::Queen, |square, all_pieces| {
+            Self::rook_attacks(square, all_pieces) | Self::bishop_attacks(square, all_pieces)
+        })
     }
 
-    fn get_pseudo_legal_queen_moves(&self, board: &Board, color: Color) -> Vec<Move> {
+    // Moves of every piece of the given type to the squares it attacks that own pieces do not occupy
+    fn get_pseudo_legal_slider_moves(
+        &self,
+        board: &Board,
+        color: Color,
+        piece_type: PieceType,
+        attacks: impl Fn(u8, u64) -> u64,
+    ) -> Vec<Move> {
         let mut moves = Vec::new();
-
-        // Select the appropriate queen bitboard and enemy pieces based on color
-        let (mut queens, enemy_pieces) = match color {
-            Color::White => (board.white_queens, board.black_pieces()),
-            Color::Black => (board.black_queens, board.white_pieces()),
-        };
-
         let all_pieces = board.all_pieces();
+        let own_pieces = board.color_pieces(color);
 
-        // Iterate through each queen position
-        while queens != 0 {
-            let from = queens.trailing_zeros() as u8;
-            queens &= queens - 1; // Clear the least significant set bit
-
-            // Queen combines rook and bishop moves
-            for &ray in &[
-                NORTH_RAYS[from as usize],
-                SOUTH_RAYS[from as usize],
-                EAST_RAYS[from as usize],
-                WEST_RAYS[from as usize],
-                NORTH_EAST_RAYS[from as usize],
-                NORTH_WEST_RAYS[from as usize],
-                SOUTH_EAST_RAYS[from as usize],
-                SOUTH_WEST_RAYS[from as usize],
-            ] {
-                self.add_ray_moves(ray, all_pieces, enemy_pieces, from, &mut moves);
-            }
+        let mut pieces = board.pieces(color, piece_type);
+        while pieces != 0 {
+            let from = pieces.trailing_zeros() as u8;
+            pieces &= pieces - 1; // Clear the least significant bit
+
+            self.add_moves_to_squares(attacks(from, all_pieces) & !own_pieces, from, &mut moves);
         }
 
         moves
     }
 
-
-    // Helper method to find the index of the first blocker on a ray
-    fn find_first_blocker_index(ray: u64, blockers: u64, reference_sq: u8) -> u32 {
-        let ray_with_blockers = ray & blockers;
-
-        // All moves northward go to higher square indices (e.g., from 10 -> 18, 26, etc.)
-        // whereas southward moves go to lower indices (e.g., from 55 -> 47, 39, etc.)
-        if reference_sq < ray_with_blockers.trailing_zeros() as u8 {
-            // Ray direction is towards higher indices (e.g., North or East)
-            ray_with_blockers.trailing_zeros()
-        } else {
-            // Ray direction is towards lower indices (e.g., South or West)
-            63 - ray_with_blockers.leading_zeros()
-        }
-    }
-
-    // Helper method to add moves to all empty squares on a bitboard
-    fn add_moves_to_empty_squares(&self, squares: u64, from: u8, moves: &mut Vec<Move>) {
+    // Helper method to add moves to all squares on a bitboard
+    fn add_moves_to_squares(&self, squares: u64, from: u8, moves: &mut Vec<Move>) {
         let mut dest_squares = squares;
         while dest_squares != 0 {
             let to = dest_squares.trailing_zeros() as u8;
@@ -639,16 +446,96 @@ impl MoveValidator {
         moves
     }
 
+    fn bitboard_to_squareset(&self, bitboard: u64) -> Vec<u8> {
+        (0..64).filter(|&i| (bitboard & (1u64 << i)) != 0).collect()
+    }
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{algebraic_to_square, square_to_algebraic};
+
+    fn validate(fen: &str, color: Color, from: &str, to: &str) -> Result<(), IllegalMoveReason> {
+        let board = Board::from_fen(fen).unwrap();
+        let (from, to) = (algebraic_to_square(from).unwrap(), algebraic_to_square(to).unwrap());
+        let is_king = board.piece_at(from).is_some_and(|(_, piece)| piece == PieceType::King);
+        let move_ = Move::new(from, to, None, is_king && from.abs_diff(to) == 2);
+        MoveValidator::new().validate_move(&board, &move_, color)
+    }
 
+    // The destinations of the legal moves from a square, in square order
+    fn destinations(fen: &str, color: Color, from: &str) -> Vec<String> {
+        let board = Board::from_fen(fen).unwrap();
+        let from = algebraic_to_square(from).unwrap();
+        let mut squares: Vec<u8> = MoveValidator::new()
+            .get_legal_moves(&board, color)
+            .iter()
+            .filter(|move_| move_.from() == from)
+            .map(Move::to)
+            .collect();
+        squares.sort();
+        squares.into_iter().map(square_to_algebraic).collect()
+    }
 
+    #[test]
+    fn pinned_pieces_stay_on_the_pin() {
+        let fen = "4k3/4r3/8/8/1b6/8/3BR3/4K3 w - -";
 
+        assert_eq!(validate(fen, Color::White, "e2", "f2"), Err(IllegalMoveReason::LeavesKingInCheck));
+        assert_eq!(validate(fen, Color::White, "e2", "e5"), Ok(()));
+        assert_eq!(validate(fen, Color::White, "e2", "e7"), Ok(()));
+        assert_eq!(validate(fen, Color::White, "d2", "e3"), Err(IllegalMoveReason::LeavesKingInCheck));
+        assert_eq!(validate(fen, Color::White, "d2", "c3"), Ok(()));
+        assert_eq!(validate(fen, Color::White, "d2", "b4"), Ok(()));
 
+        // A pinned knight cannot move at all
+        assert_eq!(destinations("4k3/4r3/8/8/8/8/4N3/4K3 w - -", Color::White, "e2"), Vec::<String>::new());
+    }
 
+    #[test]
+    fn en_passant_cannot_expose_the_king() {
+        // Both pawns leave the fifth rank, opening it to the rook
+        let fen = "8/8/8/K2pP2r/8/8/8/4k3 w - d6";
+        assert_eq!(validate(fen, Color::White, "e5", "d6"), Err(IllegalMoveReason::LeavesKingInCheck));
+        assert_eq!(validate(fen, Color::White, "e5", "e6"), Ok(()));
 
-    fn bitboard_to_squareset(&self, bitboard: u64) -> Vec<u8> {
-        (0..64).filter(|&i| (bitboard & (1u64 << i)) != 0).collect()
+        assert_eq!(validate("4k3/8/8/3pP3/8/8/8/4K3 w - d6", Color::White, "e5", "d6"), Ok(()));
+        let fen = "8/8/8/8/k2Pp2R/8/8/4K3 b - d3";
+        assert_eq!(validate(fen, Color::Black, "e4", "d3"), Err(IllegalMoveReason::LeavesKingInCheck));
     }
 
+    #[test]
+    fn castling_avoids_attacked_squares() {
+        let free = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq -";
+        assert_eq!(validate(free, Color::White, "e1", "g1"), Ok(()));
+        assert_eq!(validate(free, Color::White, "e1", "c1"), Ok(()));
+        assert_eq!(validate("r3k2r/8/8/8/8/8/8/R3K2R b KQkq -", Color::Black, "e8", "c8"), Ok(()));
+
+        // Out of check
+        let checked = "4k3/4r3/8/8/8/8/8/R3K2R w KQ -";
+        assert_eq!(validate(checked, Color::White, "e1", "g1"), Err(IllegalMoveReason::CastlingNotAllowed));
+        assert_eq!(validate(checked, Color::White, "e1", "c1"), Err(IllegalMoveReason::CastlingNotAllowed));
+
+        // Through check, on f1 or d1; an attacked b1 does not matter
+        let through = "3rkr2/8/8/8/8/8/8/R3K2R w KQ -";
+        assert_eq!(validate(through, Color::White, "e1", "g1"), Err(IllegalMoveReason::CastlingNotAllowed));
+        assert_eq!(validate(through, Color::White, "e1", "c1"), Err(IllegalMoveReason::CastlingNotAllowed));
+        assert_eq!(validate("1r2k3/8/8/8/8/8/8/R3K2R w KQ -", Color::White, "e1", "c1"), Ok(()));
+
+        // Into check
+        let into = "2r1k1r1/8/8/8/8/8/8/R3K2R w KQ -";
+        assert_eq!(validate(into, Color::White, "e1", "g1"), Err(IllegalMoveReason::LeavesKingInCheck));
+        assert_eq!(validate(into, Color::White, "e1", "c1"), Err(IllegalMoveReason::LeavesKingInCheck));
+    }
 
+    #[test]
+    fn moves_do_not_wrap_around_the_board() {
+        assert_eq!(destinations("4k3/8/8/8/K7/8/8/8 w - -", Color::White, "a4"), ["a3", "b3", "b4", "a5", "b5"]);
+        assert_eq!(destinations("k7/8/8/7K/8/8/8/8 w - -", Color::White, "h5"), ["g4", "h4", "g5", "g6", "h6"]);
+        assert_eq!(destinations("4k3/8/8/8/7N/8/8/4K3 w - -", Color::White, "h4"), ["g2", "f3", "f5", "g6"]);
+        assert_eq!(destinations("4k3/8/8/8/8/8/8/N3K3 w - -", Color::White, "a1"), ["c2", "b3"]);
+        assert_eq!(destinations("4k3/8/8/8/N7/8/8/4K3 w - -", Color::White, "a4"), ["b2", "c3", "c5", "b6"]);
+        assert_eq!(destinations("n3k3/8/8/8/8/8/8/4K2N w - -", Color::White, "h1"), ["f2", "g3"]);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,185 @@
+use crate::board::Board;
+use crate::move_::Move;
+use crate::move_validator::MoveValidator;
+use crate::types::Color;
+
+// Counts the leaf nodes of the legal move tree to the given depth
+pub fn perft(move_validator: &MoveValidator, board: &mut Board, color: Color, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = move_validator.get_legal_moves(board, color);
+
+    // Every legal move at the last ply is a leaf, no need to play them
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for move_ in &moves {
+        let undo = board.make_move(move_);
+        nodes += perft(move_validator, board, color.opponent(), depth - 1);
+        board.unmake_move(move_, &undo);
+    }
+
+    nodes
+}
+
+// Perft split by root move, to narrow down which subtree disagrees with a reference count
+pub fn divide(move_validator: &MoveValidator, board: &mut Board, color: Color, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    move_validator
+        .get_legal_moves(board, color)
+        .into_iter()
+        .map(|move_| {
+            let undo = board.make_move(&move_);
+            let nodes = perft(move_validator, board, color.opponent(), depth - 1);
+            board.unmake_move(&move_, &undo);
+            (move_, nodes)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::ray_walk;
+
+    // Reference positions and node counts from https://www.chessprogramming.org/Perft_Results
+    fn assert_perft(fen: &str, color: Color, expected: &[u64]) {
+        let move_validator = MoveValidator::new();
+        let mut board = Board::from_fen(fen).unwrap();
+
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(
+                perft(&move_validator, &mut board, color, depth as u32 + 1),
+                nodes,
+                "perft({}) of '{}'",
+                depth + 1,
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn start_position() {
+        assert_perft(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            Color::White,
+            &[20, 400, 8_902, 197_281],
+        );
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            Color::White,
+            &[48, 2_039, 97_862],
+        );
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            Color::White,
+            &[14, 191, 2_812, 43_238],
+        );
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            Color::White,
+            &[6, 264, 9_467],
+        );
+    }
+
+    #[test]
+    fn position_4_mirrored() {
+        assert_perft(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            Color::Black,
+            &[6, 264, 9_467],
+        );
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            Color::White,
+            &[44, 1_486, 62_379],
+        );
+    }
+
+    #[test]
+    fn position_6() {
+        assert_perft(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            Color::White,
+            &[46, 2_079, 89_890],
+        );
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let move_validator = MoveValidator::new();
+        let mut board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+
+        let split = divide(&move_validator, &mut board, Color::White, 2);
+
+        assert_eq!(split.len(), 48);
+        assert_eq!(split.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2_039);
+    }
+
+    // Times perft with the old ray walk and with the magic lookups on the same positions, taking
+    // the best of a few alternating rounds to keep out noise from the rest of the machine:
+    // cargo test --release ray_walk_against_magic -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn ray_walk_against_magic() {
+        const ROUNDS: usize = 5;
+        let move_validator = MoveValidator::new();
+
+        for (name, fen, depth, expected) in [
+            ("start", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 5, 4_865_609),
+            ("kiwipete", "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 4, 4_085_603),
+        ] {
+            let mut board = Board::from_fen(fen).unwrap();
+            let mut time = |with_rays: bool| {
+                let started = Instant::now();
+                let nodes = if with_rays {
+                    ray_walk::with_ray_walk(|| perft(&move_validator, &mut board, Color::White, depth))
+                } else {
+                    perft(&move_validator, &mut board, Color::White, depth)
+                };
+                assert_eq!(nodes, expected);
+                started.elapsed()
+            };
+
+            let (mut rays, mut magic) = (Duration::MAX, Duration::MAX);
+            for _ in 0..ROUNDS {
+                rays = rays.min(time(true));
+                magic = magic.min(time(false));
+            }
+            println!(
+                "{} perft({}): ray walk {:.0?} ({:.1}M nodes/s), magic {:.0?} ({:.1}M nodes/s)",
+                name,
+                depth,
+                rays,
+                expected as f64 / rays.as_secs_f64() / 1e6,
+                magic,
+                expected as f64 / magic.as_secs_f64() / 1e6,
+            );
+        }
+    }
+}
@@ -0,0 +1,655 @@
+use std::io::BufRead;
+
+use crate::board::{Board, STARTING_FEN};
+use crate::error::PgnError;
+use crate::move_::Move;
+use crate::types::{Color, GameResult};
+
+// The Seven Tag Roster, in its required order, with the values used when a tag is unknown.
+// The Result tag is always derived from the game itself.
+const SEVEN_TAG_ROSTER: [(&str, &str); 6] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+];
+
+// Maximum length of a movetext line, as recommended by the PGN export format
+const MAX_LINE_LENGTH: usize = 79;
+
+// Writes a game in PGN export format. `tags` may override roster values and add custom tags;
+// games that did not start from the initial position get the SetUp and FEN tags.
+pub fn write_pgn(start_fen: &str, moves: &[Move], result: Option<&GameResult>, tags: &[(&str, &str)]) -> String {
+    let result_token = result_token(result);
+    let mut pgn = String::new();
+
+    for (name, default) in SEVEN_TAG_ROSTER {
+        let value = tags.iter().find(|(tag, _)| *tag == name).map_or(default, |(_, value)| value);
+        pgn.push_str(&format_tag(name, value));
+    }
+    pgn.push_str(&format_tag("Result", result_token));
+
+    if start_fen != STARTING_FEN {
+        pgn.push_str(&format_tag("SetUp", "1"));
+        pgn.push_str(&format_tag("FEN", start_fen));
+    }
+
+    for (name, value) in tags {
+        let is_reserved = SEVEN_TAG_ROSTER.iter().any(|(tag, _)| tag == name)
+            || matches!(*name, "Result" | "SetUp" | "FEN");
+        if !is_reserved {
+            pgn.push_str(&format_tag(name, value));
+        }
+    }
+
+    pgn.push('\n');
+    pgn.push_str(&movetext(start_fen, moves, result_token));
+    pgn.push('\n');
+
+    pgn
+}
+
+fn movetext(start_fen: &str, moves: &[Move], result_token: &str) -> String {
+    let mut board = Board::from_fen(start_fen).unwrap_or_else(|_| Board::new());
+    let fields: Vec<&str> = start_fen.split_whitespace().collect();
+    let mut turn = if fields.get(1) == Some(&"b") { Color::Black } else { Color::White };
+    let mut move_number: u32 = fields.get(5).and_then(|field| field.parse().ok()).unwrap_or(1);
+
+    let mut tokens = Vec::new();
+
+    for (index, move_) in moves.iter().enumerate() {
+        match turn {
+            Color::White => tokens.push(format!("{}.", move_number)),
+            Color::Black if index == 0 => tokens.push(format!("{}...", move_number)),
+            Color::Black => {}
+        }
+
+        tokens.push(move_.to_san(&board));
+        board.make_move(move_);
+
+        if turn == Color::Black {
+            move_number += 1;
+        }
+        turn = turn.opponent();
+    }
+
+    tokens.push(result_token.to_string());
+
+    // Fill lines up to the maximum length
+    let mut text = String::new();
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        line_length += token.len();
+        text.push_str(&token);
+    }
+
+    text
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, escaped)
+}
+
+fn result_token(result: Option<&GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::WhiteWin) => "1-0",
+        Some(GameResult::BlackWin) => "0-1",
+        Some(GameResult::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+// A game read from PGN, with its mainline replayed into legal moves
+#[derive(Clone, Debug)]
+pub struct PgnGame {
+    tags: Vec<(String, String)>,
+    start_fen: String,
+    moves: Vec<PgnMove>,
+    result: Option<GameResult>,
+}
+
+impl PgnGame {
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn start_fen(&self) -> &str {
+        &self.start_fen
+    }
+
+    pub fn moves(&self) -> &[PgnMove] {
+        &self.moves
+    }
+
+    // The mainline as plain moves, ready to be applied from the start position
+    pub fn mainline(&self) -> Vec<Move> {
+        self.moves.iter().map(|pgn_move| pgn_move.move_).collect()
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+}
+
+// A move of the movetext together with its annotations and the variations that replace it
+#[derive(Clone, Debug)]
+pub struct PgnMove {
+    move_: Move,
+    san: String,
+    nags: Vec<u8>,
+    comments_before: Vec<String>,
+    comments: Vec<String>,
+    variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    pub fn move_(&self) -> Move {
+        self.move_
+    }
+
+    // The SAN exactly as written in the file
+    pub fn san(&self) -> &str {
+        &self.san
+    }
+
+    pub fn nags(&self) -> &[u8] {
+        &self.nags
+    }
+
+    pub fn comments_before(&self) -> &[String] {
+        &self.comments_before
+    }
+
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    // Alternative lines starting from the position before this move
+    pub fn variations(&self) -> &[Vec<PgnMove>] {
+        &self.variations
+    }
+}
+
+// Reads the games of a PGN file one at a time. A game that fails to parse yields an error
+// naming the game index (starting at 1) and the ply, and reading continues with the next game.
+pub struct PgnReader<R> {
+    reader: R,
+    game_index: usize,
+    pending_line: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, game_index: 0, pending_line: None }
+    }
+
+    // Collects the lines of the next game: its tag section and movetext, up to the
+    // tag section of the following game or the end of the input
+    fn read_game_text(&mut self) -> Result<Option<String>, PgnError> {
+        let mut text = String::new();
+        let mut in_movetext = false;
+        let mut in_comment = false;
+        let mut depth = 0usize;
+
+        loop {
+            let line = match self.pending_line.take() {
+                Some(line) => line,
+                None => {
+                    let mut line = String::new();
+                    let read = self
+                        .reader
+                        .read_line(&mut line)
+                        .map_err(|error| PgnError::Io { game: self.game_index + 1, error })?;
+                    if read == 0 {
+                        break;
+                    }
+                    line
+                }
+            };
+
+            let trimmed = line.trim_start();
+
+            if !in_comment && trimmed.starts_with('[') && in_movetext {
+                self.pending_line = Some(line);
+                break;
+            }
+
+            // Escape lines are reserved for external tools
+            if !in_comment && line.starts_with('%') {
+                continue;
+            }
+
+            if !in_comment && trimmed.starts_with('[') {
+                text.push_str(&line);
+                continue;
+            }
+
+            if !trimmed.is_empty() {
+                in_movetext = true;
+            }
+
+            // Track comments and variations so that a termination marker ends the game
+            let mut word = String::new();
+            let mut is_complete = false;
+            for c in line.chars().chain(std::iter::once('\n')) {
+                if in_comment {
+                    in_comment = c != '}';
+                    continue;
+                }
+
+                if c.is_whitespace() || matches!(c, '{' | '(' | ')' | ';') {
+                    is_complete |= depth == 0 && parse_result(&word).is_some();
+                    word.clear();
+                } else {
+                    word.push(c);
+                }
+
+                match c {
+                    '{' => in_comment = true,
+                    '(' => depth += 1,
+                    ')' => depth = depth.saturating_sub(1),
+                    ';' => break,
+                    _ => {}
+                }
+            }
+
+            text.push_str(&line);
+
+            if is_complete {
+                break;
+            }
+        }
+
+        Ok(if text.trim().is_empty() { None } else { Some(text) })
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = match self.read_game_text() {
+            Ok(Some(text)) => text,
+            Ok(None) => return None,
+            Err(error) => return Some(Err(error)),
+        };
+
+        self.game_index += 1;
+        Some(parse_game(&text, self.game_index))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    OpenVariation,
+    CloseVariation,
+    Result(Option<GameResult>),
+    San(String),
+}
+
+fn parse_game(text: &str, game_index: usize) -> Result<PgnGame, PgnError> {
+    let tokens = tokenize(text).map_err(|message| PgnError::Syntax { game: game_index, message })?;
+
+    let tags: Vec<(String, String)> = tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Tag(name, value) => Some((name.clone(), value.clone())),
+            _ => None,
+        })
+        .collect();
+
+    let start_fen = tags
+        .iter()
+        .find(|(name, _)| name == "FEN")
+        .map_or(STARTING_FEN.to_string(), |(_, value)| value.clone());
+
+    let board = Board::from_fen(&start_fen)
+        .map_err(|error| PgnError::InvalidFen { game: game_index, error })?;
+    let turn = if start_fen.split_whitespace().nth(1) == Some("b") { Color::Black } else { Color::White };
+
+    let mut parser = MovetextParser {
+        tokens: tokens.into_iter().filter(|token| !matches!(token, Token::Tag(..))).collect(),
+        position: 0,
+        game_index,
+        result: None,
+    };
+    let moves = parser.parse_line(&board, turn, 1, 0)?;
+
+    // Without a termination marker, fall back on the Result tag
+    let result = parser.result.unwrap_or_else(|| {
+        tags.iter()
+            .find(|(name, _)| name == "Result")
+            .and_then(|(_, value)| parse_result(value))
+            .flatten()
+    });
+
+    Ok(PgnGame { tags, start_fen, moves, result })
+}
+
+struct MovetextParser {
+    tokens: Vec<Token>,
+    position: usize,
+    game_index: usize,
+    result: Option<Option<GameResult>>,
+}
+
+impl MovetextParser {
+    // Replays a line of moves from the given position, descending into variations.
+    // `ply` is the game ply of the first move of the line.
+    fn parse_line(&mut self, board: &Board, turn: Color, ply: usize, depth: usize) -> Result<Vec<PgnMove>, PgnError> {
+        let mut board = board.clone();
+        let mut turn = turn;
+        let mut ply = ply;
+        let mut line: Vec<PgnMove> = Vec::new();
+        let mut pending_comments = Vec::new();
+        // Position before the last move of the line, where its variations start
+        let mut previous: Option<(Board, Color)> = None;
+
+        while self.position < self.tokens.len() {
+            let token = self.tokens[self.position].clone();
+            self.position += 1;
+
+            match token {
+                Token::Comment(comment) => match line.last_mut() {
+                    Some(last) if pending_comments.is_empty() => last.comments.push(comment),
+                    _ => pending_comments.push(comment),
+                },
+                Token::Nag(nag) => {
+                    if let Some(last) = line.last_mut() {
+                        last.nags.push(nag);
+                    }
+                }
+                Token::OpenVariation => {
+                    let (Some(last), Some((before, before_turn))) = (line.last_mut(), previous.as_ref()) else {
+                        return Err(self.movetext_error(ply, "variation without a preceding move"));
+                    };
+                    let variation = self.parse_line(before, *before_turn, ply - 1, depth + 1)?;
+                    last.variations.push(variation);
+                }
+                Token::CloseVariation => {
+                    if depth == 0 {
+                        return Err(self.movetext_error(ply, "unexpected ')'"));
+                    }
+                    return Ok(line);
+                }
+                Token::Result(result) => {
+                    if depth > 0 {
+                        return Err(self.movetext_error(ply, "unterminated variation"));
+                    }
+                    self.result = Some(result);
+                    return Ok(line);
+                }
+                Token::San(san) => {
+                    let move_ = Move::from_san(&san, &board, turn).map_err(|error| PgnError::IllegalMove {
+                        game: self.game_index,
+                        ply,
+                        san: san.clone(),
+                        error,
+                    })?;
+
+                    previous = Some((board.clone(), turn));
+                    board.make_move(&move_);
+                    turn = turn.opponent();
+                    ply += 1;
+
+                    line.push(PgnMove {
+                        move_,
+                        san,
+                        nags: Vec::new(),
+                        comments_before: std::mem::take(&mut pending_comments),
+                        comments: Vec::new(),
+                        variations: Vec::new(),
+                    });
+                }
+                Token::Tag(..) => {}
+            }
+        }
+
+        if depth > 0 {
+            return Err(self.movetext_error(ply, "unterminated variation"));
+        }
+
+        Ok(line)
+    }
+
+    fn movetext_error(&self, ply: usize, message: &'static str) -> PgnError {
+        PgnError::Movetext { game: self.game_index, ply, message }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+                let name: String = std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace() && *c != '"')).collect();
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                if chars.next() != Some('"') {
+                    return Err(format!("malformed tag '{}'", name));
+                }
+
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => value.extend(chars.next()),
+                        Some('"') => break,
+                        Some(c) => value.push(c),
+                        None => return Err(format!("unterminated value of tag '{}'", name)),
+                    }
+                }
+
+                while chars.next_if(|c| *c != ']').is_some() {}
+                chars.next();
+                tokens.push(Token::Tag(name, value));
+            }
+            '{' => {
+                chars.next();
+                let comment: String = std::iter::from_fn(|| chars.next_if(|c| *c != '}')).collect();
+                if chars.next().is_none() {
+                    return Err("unterminated comment".to_string());
+                }
+                tokens.push(Token::Comment(comment.split_whitespace().collect::<Vec<_>>().join(" ")));
+            }
+            ';' => {
+                chars.next();
+                let comment: String = std::iter::from_fn(|| chars.next_if(|c| *c != '\n')).collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::OpenVariation);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::CloseVariation);
+            }
+            '$' => {
+                chars.next();
+                let digits: String = std::iter::from_fn(|| chars.next_if(|c| c.is_ascii_digit())).collect();
+                let nag = digits.parse().map_err(|_| format!("invalid NAG '${}'", digits))?;
+                tokens.push(Token::Nag(nag));
+            }
+            _ => {
+                let symbol: String = std::iter::from_fn(|| {
+                    chars.next_if(|c| !c.is_whitespace() && !matches!(c, '{' | '}' | '(' | ')' | ';' | '$' | '['))
+                })
+                .collect();
+
+                if let Some(result) = parse_result(&symbol) {
+                    tokens.push(Token::Result(result));
+                    continue;
+                }
+
+                // Strip a move number such as "12." or "12..." that may be glued to the move
+                let san = match symbol.find(|c: char| !c.is_ascii_digit()) {
+                    Some(index) if index > 0 && symbol[index..].starts_with('.') => symbol[index..].trim_start_matches('.'),
+                    Some(_) => symbol.as_str(),
+                    None => "",
+                };
+
+                // A detached en passant marker adds nothing to the move before it
+                if san.is_empty() || san == "e.p." {
+                    continue;
+                }
+
+                // Traditional suffix annotations become their NAG equivalents
+                let annotation_start = san.find(['!', '?']).unwrap_or(san.len());
+                let (san, annotation) = san.split_at(annotation_start);
+                tokens.push(Token::San(san.to_string()));
+
+                let nag = match annotation {
+                    "" => None,
+                    "!" => Some(1),
+                    "?" => Some(2),
+                    "!!" => Some(3),
+                    "??" => Some(4),
+                    "!?" => Some(5),
+                    "?!" => Some(6),
+                    other => return Err(format!("invalid move annotation '{}'", other)),
+                };
+                tokens.extend(nag.map(Token::Nag));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Parses a game termination marker; the outer None means the text is not one
+fn parse_result(text: &str) -> Option<Option<GameResult>> {
+    match text {
+        "1-0" => Some(Some(GameResult::WhiteWin)),
+        "0-1" => Some(Some(GameResult::BlackWin)),
+        "1/2-1/2" => Some(Some(GameResult::Draw)),
+        "*" => Some(None),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(fen: &str, color: Color, ucis: &[&str]) -> Vec<Move> {
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut turn = color;
+        ucis.iter()
+            .map(|uci| {
+                let move_ = Move::from_uci(uci, &board, turn).unwrap();
+                board.make_move(&move_);
+                turn = turn.opponent();
+                move_
+            })
+            .collect()
+    }
+
+    fn ucis(moves: &[Move]) -> Vec<String> {
+        moves.iter().map(|move_| move_.to_string()).collect()
+    }
+
+    #[test]
+    fn writes_finished_game() {
+        let moves = play(STARTING_FEN, Color::White, &["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7"]);
+        let pgn = write_pgn(STARTING_FEN, &moves, Some(&GameResult::WhiteWin), &[("White", "Alice"), ("Annotator", "Bob \"B\"")]);
+
+        assert_eq!(
+            pgn,
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"Alice\"]\n[Black \"?\"]\n\
+             [Result \"1-0\"]\n[Annotator \"Bob \\\"B\\\"\"]\n\n1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0\n"
+        );
+    }
+
+    #[test]
+    fn writes_setup_for_custom_start() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 12";
+        let moves = play(fen, Color::Black, &["e8d7", "e2e4"]);
+        let pgn = write_pgn(fen, &moves, None, &[]);
+
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n"));
+        assert!(pgn.ends_with("\n12... Kd7 13. e4 *\n"));
+    }
+
+    #[test]
+    fn reads_multiple_games_with_variations() {
+        let text = "\
+[Event \"First\"]
+[White \"Alice\"]
+[Result \"1-0\"]
+
+1. e4 {best by test} e5 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3!? $14 Nc6
+; a rest-of-line comment
+3. Bb5 a6 1-0
+
+[Event \"Second\"]
+[SetUp \"1\"]
+[FEN \"4k3/8/8/3pP3/8/8/8/4K2R w K d6 0 1\"]
+
+1. exd6 e.p. Kd7 2. 0-0 Kxd6 *
+";
+        let games: Vec<PgnGame> = PgnReader::new(text.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(games.len(), 2);
+
+        let first = &games[0];
+        assert_eq!(first.tag("White"), Some("Alice"));
+        assert_eq!(first.result(), Some(GameResult::WhiteWin));
+        assert_eq!(ucis(&first.mainline()), ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6"]);
+        assert_eq!(first.moves()[0].comments(), ["best by test"]);
+        assert_eq!(first.moves()[2].nags(), [5, 14]);
+        assert_eq!(first.moves()[3].comments(), ["a rest-of-line comment"]);
+
+        let variation = &first.moves()[1].variations()[0];
+        assert_eq!(variation.iter().map(|pgn_move| pgn_move.san()).collect::<Vec<_>>(), ["c5", "Nf3", "d6"]);
+        assert_eq!(variation[1].variations()[0][0].move_().to_string(), "c2c3");
+
+        let second = &games[1];
+        assert_eq!(second.start_fen(), "4k3/8/8/3pP3/8/8/8/4K2R w K d6 0 1");
+        assert_eq!(second.result(), None);
+        assert_eq!(ucis(&second.mainline()), ["e5d6", "e8d7", "e1g1", "d7d6"]);
+    }
+
+    #[test]
+    fn reports_game_and_ply_of_bad_moves() {
+        let text = "1. e4 e5 *\n\n[Event \"?\"]\n\n1. e4 e5 2. Ke3 *\n\n1. d4 *\n";
+        let games: Vec<Result<PgnGame, PgnError>> = PgnReader::new(text.as_bytes()).collect();
+
+        assert_eq!(games.len(), 3);
+        assert!(games[0].is_ok());
+        let error = games[1].as_ref().unwrap_err();
+        assert!(matches!(error, PgnError::IllegalMove { game: 2, ply: 3, san, .. } if san == "Ke3"));
+        assert!(error.to_string().starts_with("Game 2, ply 3: cannot play 'Ke3'"));
+        assert!(games[2].is_ok());
+    }
+
+    #[test]
+    fn exported_games_read_back() {
+        let moves = play(STARTING_FEN, Color::White, &["d2d4", "d7d5", "c2c4", "d5c4", "e2e3", "b7b5", "a2a4", "c7c6"]);
+        let pgn = write_pgn(STARTING_FEN, &moves, Some(&GameResult::Draw), &[]);
+
+        let game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(game.mainline(), moves);
+        assert_eq!(game.result(), Some(GameResult::Draw));
+    }
+}
@@ -1,20 +1,425 @@
+use std::io::{self, BufRead, BufReader, Stdin, Stdout, Write};
+use std::time::Duration;
+
 use rand::{Rng};
 
+use crate::board::Board;
+use crate::error::NotationError;
 use crate::move_::Move;
+use crate::render::BoardRenderer;
+use crate::search::{time_budget, SearchLimits, Searcher};
+use crate::types::{Color, DrawReason, TimeControl};
+
+// How deep EnginePlayer::new searches every move
+const DEFAULT_ENGINE_DEPTH: u32 = 4;
+
+// Everything a player may look at when it is asked to move
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerView<'a> {
+    pub(crate) board: &'a Board,
+    pub(crate) turn: Color,
+    pub(crate) start_fen: &'a str,
+    pub(crate) move_history: &'a [Move],
+    pub(crate) legal_moves: &'a [Move],
+    pub(crate) halfmove_clock: u32,
+    pub(crate) fullmove_number: u32,
+    pub(crate) draw_claim: Option<DrawReason>,
+    pub(crate) time_control: Option<TimeControl>,
+    // Remaining time of white and black, when the game is played with a clock
+    pub(crate) time_left: Option<[Duration; 2]>,
+}
+
+impl<'a> PlayerView<'a> {
+    pub fn board(&self) -> &'a Board {
+        self.board
+    }
+
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    // The position the game started from; replaying the move history from it reaches the board
+    pub fn start_fen(&self) -> &'a str {
+        self.start_fen
+    }
+
+    pub fn move_history(&self) -> &'a [Move] {
+        self.move_history
+    }
+
+    pub fn legal_moves(&self) -> &'a [Move] {
+        self.legal_moves
+    }
+
+    pub fn fen(&self) -> String {
+        format!("{} {} {}", self.board.to_fen(self.turn), self.halfmove_clock, self.fullmove_number)
+    }
 
+    // A draw the player could claim instead of moving, see PlayerAction::ClaimDraw
+    pub fn draw_claim(&self) -> Option<DrawReason> {
+        self.draw_claim
+    }
+
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+
+    pub fn time_left(&self, color: Color) -> Option<Duration> {
+        self.time_left.map(|time_left| time_left[color as usize])
+    }
+}
+
+// What a player does on its turn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PlayerAction {
+    Move(Move),
+    // Plays the move and offers the opponent a draw
+    OfferDraw(Move),
+    // Claims a threefold repetition or fifty-move draw instead of moving
+    ClaimDraw,
+    Resign,
+    // Takes back the player's last move and the opponent's reply
+    Undo,
+}
 
 pub trait Player {
-    fn select_move<'a>(&self, moves: &'a[Move]) -> &'a Move;
+    fn select_move(&mut self, view: &PlayerView) -> PlayerAction;
+
+    // Called when the opponent offers a draw, with the player to move next
+    fn accept_draw(&mut self, _view: &PlayerView) -> bool {
+        false
+    }
 }
 
 #[derive(Default)]
 pub struct RandomPlayer;
 
 impl Player for RandomPlayer {
-    fn select_move<'a>(&self, moves: &'a[Move]) -> &'a Move {
+    fn select_move(&mut self, view: &PlayerView) -> PlayerAction {
+        let moves = view.legal_moves();
         let random_index = rand::rng().random_range(0..moves.len());
-        &moves[random_index]
+        PlayerAction::Move(moves[random_index])
     }
 }
 
-// todo: Implement CLI player
\ No newline at end of file
+// A computer player searching with alpha-beta, see Searcher
+pub struct EnginePlayer {
+    searcher: Searcher,
+    limits: SearchLimits,
+}
+
+impl EnginePlayer {
+    pub fn new() -> Self {
+        Self::with_limits(SearchLimits::new().depth(DEFAULT_ENGINE_DEPTH))
+    }
+
+    // When the game has a clock, the engine also keeps to its share of the remaining time
+    pub fn with_limits(limits: SearchLimits) -> Self {
+        Self { searcher: Searcher::new(), limits }
+    }
+
+    // Keeps to a slice of the remaining time, see time_budget
+    fn limits_for(&self, view: &PlayerView) -> SearchLimits {
+        let Some(time_left) = view.time_left(view.turn()) else {
+            return self.limits;
+        };
+
+        let increment = view.time_control().map_or(Duration::ZERO, |time_control| time_control.increment());
+        let budget = time_budget(time_left, increment, None);
+
+        match self.limits.get_time() {
+            Some(time) if time < budget => self.limits,
+            _ => self.limits.time(budget),
+        }
+    }
+}
+
+impl Default for EnginePlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Player for EnginePlayer {
+    fn select_move(&mut self, view: &PlayerView) -> PlayerAction {
+        // Keys of the positions played so far, so that the search can avoid or aim for repetitions
+        let mut history = Vec::with_capacity(view.move_history().len());
+        if let Ok(mut board) = Board::from_fen(view.start_fen()) {
+            for move_ in view.move_history() {
+                history.push(board.hash());
+                board.make_move(move_);
+            }
+        }
+
+        let limits = self.limits_for(view);
+        match self.searcher.search(view.board(), view.turn(), &history, limits, |_| {}) {
+            Some(info) => PlayerAction::Move(info.best_move()),
+            None => PlayerAction::Resign,
+        }
+    }
+}
+
+// A human playing from a terminal, entering moves in SAN or UCI notation
+pub struct CliPlayer<R = BufReader<Stdin>, W = Stdout> {
+    input: R,
+    output: W,
+    renderer: BoardRenderer,
+    // Set by the "draw" command, the offer goes out with the next move
+    offering_draw: bool,
+}
+
+impl CliPlayer {
+    pub fn new() -> Self {
+        // Stdin is buffered already; a one-byte buffer leaves the input nobody has read yet
+        // to the other player when two humans share the terminal
+        Self::with_io(BufReader::with_capacity(1, io::stdin()), io::stdout())
+    }
+}
+
+impl Default for CliPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: BufRead, W: Write> CliPlayer<R, W> {
+    pub fn with_io(input: R, output: W) -> Self {
+        Self { input, output, renderer: BoardRenderer::new(), offering_draw: false }
+    }
+
+    // How the board is drawn; the perspective and last move follow the game
+    pub fn renderer(mut self, renderer: BoardRenderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    // Reads a trimmed line, or None once the input is exhausted
+    fn read_line(&mut self) -> Option<String> {
+        let mut line = String::new();
+        match self.input.read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim().to_string()),
+        }
+    }
+
+    fn write_position(&mut self, view: &PlayerView) -> io::Result<()> {
+        let renderer = self.renderer.perspective(view.turn()).last_move(view.move_history().last().copied());
+        writeln!(self.output)?;
+        writeln!(self.output, "{}", renderer.render(view.board()))?;
+
+        if let Some(last) = view.move_history().last() {
+            writeln!(self.output, "Last move: {}", last)?;
+        }
+        if let (Some(white), Some(black)) = (view.time_left(Color::White), view.time_left(Color::Black)) {
+            writeln!(self.output, "Clock: white {:.1}s, black {:.1}s", white.as_secs_f64(), black.as_secs_f64())?;
+        }
+        if view.board().in_check(view.turn()) {
+            writeln!(self.output, "{} is in check", view.turn())?;
+        }
+
+        Ok(())
+    }
+
+    // Resolves UCI input first, as SAN cannot be mistaken for it, then SAN
+    fn parse_move(text: &str, view: &PlayerView) -> Result<Move, NotationError> {
+        match Move::from_uci(text, view.board(), view.turn()) {
+            Err(NotationError::Malformed { .. }) => Move::from_san(text, view.board(), view.turn()),
+            result => result,
+        }
+    }
+
+    // The legal moves in SAN, separated by spaces
+    fn legal_moves(view: &PlayerView) -> String {
+        let moves: Vec<String> = view.legal_moves().iter().map(|move_| move_.to_san(view.board())).collect();
+        moves.join(" ")
+    }
+
+    // Handles one line of input, returning the action once the player has settled on one
+    fn handle_command(&mut self, command: &str, view: &PlayerView) -> io::Result<Option<PlayerAction>> {
+        match command {
+            "" => {}
+            "help" => {
+                writeln!(self.output, "Enter a move in SAN (Nf3, exd5, O-O) or UCI (g1f3) notation, or a command:")?;
+                writeln!(self.output, "  moves   list the legal moves")?;
+                writeln!(self.output, "  fen     print the position as FEN")?;
+                writeln!(self.output, "  draw    claim a draw, or offer one with your next move")?;
+                writeln!(self.output, "  undo    take back your last move")?;
+                writeln!(self.output, "  resign  resign the game")?;
+            }
+            "moves" => writeln!(self.output, "{}", Self::legal_moves(view))?,
+            "fen" => writeln!(self.output, "{}", view.fen())?,
+            "resign" => return Ok(Some(PlayerAction::Resign)),
+            "draw" => {
+                if let Some(reason) = view.draw_claim() {
+                    writeln!(self.output, "Claiming a draw by {}", reason)?;
+                    return Ok(Some(PlayerAction::ClaimDraw));
+                }
+                self.offering_draw = true;
+                writeln!(self.output, "Your draw offer will be made with your next move")?;
+            }
+            "undo" => {
+                if view.move_history().len() >= 2 {
+                    return Ok(Some(PlayerAction::Undo));
+                }
+                writeln!(self.output, "There is no move of yours to take back")?;
+            }
+            _ => match Self::parse_move(command, view) {
+                Ok(move_) if std::mem::take(&mut self.offering_draw) => return Ok(Some(PlayerAction::OfferDraw(move_))),
+                Ok(move_) => return Ok(Some(PlayerAction::Move(move_))),
+                // An ambiguous move lists its candidates already
+                Err(error @ NotationError::Illegal { .. }) => {
+                    writeln!(self.output, "{}. Legal moves: {}", error, Self::legal_moves(view))?
+                }
+                Err(error) => writeln!(self.output, "{}. Type 'help' for the commands.", error)?,
+            },
+        }
+
+        Ok(None)
+    }
+}
+
+impl<R: BufRead, W: Write> Player for CliPlayer<R, W> {
+    fn select_move(&mut self, view: &PlayerView) -> PlayerAction {
+        // A closed terminal cannot be recovered from; the game is then resigned
+        let _ = self.write_position(view);
+
+        loop {
+            let _ = write!(self.output, "{} to move: ", view.turn());
+            let _ = self.output.flush();
+
+            let Some(command) = self.read_line() else {
+                return PlayerAction::Resign;
+            };
+
+            match self.handle_command(&command, view) {
+                Ok(Some(action)) => return action,
+                Ok(None) => {}
+                Err(_) => return PlayerAction::Resign,
+            }
+        }
+    }
+
+    fn accept_draw(&mut self, view: &PlayerView) -> bool {
+        let _ = self.write_position(view);
+
+        loop {
+            let _ = write!(self.output, "Your opponent offers a draw. Accept? [y/n] ");
+            let _ = self.output.flush();
+
+            match self.read_line().map(|answer| answer.to_ascii_lowercase()).as_deref() {
+                Some("y" | "yes") => return true,
+                Some("n" | "no") | None => return false,
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::STARTING_FEN;
+    use crate::game::Game;
+    use crate::move_validator::MoveValidator;
+    use crate::types::GameResult;
+
+    fn cli_player(input: &str) -> CliPlayer<&[u8], Vec<u8>> {
+        CliPlayer::with_io(input.as_bytes(), Vec::new())
+    }
+
+    #[test]
+    fn engine_player_plays_the_best_move() {
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        let mut game = Game::from_fen(fen, Box::new(EnginePlayer::new()), Box::new(RandomPlayer)).unwrap();
+        game.step().unwrap();
+        assert_eq!(game.get_game_result(), Some(GameResult::WhiteWin));
+
+        let mut game = Game::new(Box::new(EnginePlayer::new()), Box::new(EnginePlayer::new()));
+        game.set_time_control(TimeControl::new(Duration::from_secs(3), Duration::ZERO));
+        game.step().unwrap();
+        game.step().unwrap();
+        assert!(game.time_left(Color::White).unwrap() > Duration::from_secs(2));
+    }
+
+    #[test]
+    fn cli_player_reads_san_and_uci() {
+        let mut game = Game::new(Box::new(cli_player("e4\nd7d5\n")), Box::new(cli_player("e7e5\nNf3\n")));
+        game.step().unwrap();
+        game.step().unwrap();
+
+        let history: Vec<String> = game.move_history().iter().map(|move_| move_.to_string()).collect();
+        assert_eq!(history, ["e2e4", "e7e5"]);
+    }
+
+    #[test]
+    fn cli_player_reprompts_on_bad_input() {
+        let board = Board::from_fen("rnbqkb1r/ppp2ppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        let legal_moves = MoveValidator::new().get_legal_moves(&board, Color::Black);
+        let view = PlayerView {
+            board: &board,
+            turn: Color::Black,
+            start_fen: STARTING_FEN,
+            move_history: &[],
+            legal_moves: &legal_moves,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            draw_claim: None,
+            time_control: None,
+            time_left: None,
+        };
+
+        let mut player = cli_player("Nd7\nQd9\nKe6\nNbd7\n");
+        assert_eq!(player.select_move(&view), PlayerAction::Move(Move::from_san("Nbd7", &board, Color::Black).unwrap()));
+
+        let output = String::from_utf8(player.output).unwrap();
+        assert!(output.contains("Ambiguous move 'Nd7', candidates: Nfd7, Nbd7"));
+        assert!(output.contains("Invalid move 'Qd9'"));
+        assert!(output.contains("Illegal move 'Ke6' for Black. Legal moves: "));
+        assert!(output.contains(" Nbd7 "));
+        assert!(output.contains("Black to move: "));
+    }
+
+    #[test]
+    fn cli_player_speaks_plain_words() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1").unwrap();
+        let legal_moves = MoveValidator::new().get_legal_moves(&board, Color::White);
+        let view = PlayerView {
+            board: &board,
+            turn: Color::White,
+            start_fen: STARTING_FEN,
+            move_history: &[],
+            legal_moves: &legal_moves,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            draw_claim: Some(DrawReason::ThreefoldRepetition),
+            time_control: None,
+            time_left: None,
+        };
+
+        let mut player = cli_player("draw\n");
+        assert_eq!(player.select_move(&view), PlayerAction::ClaimDraw);
+
+        let output = String::from_utf8(player.output).unwrap();
+        assert!(output.contains("White is in check"));
+        assert!(output.contains("White to move: "));
+        assert!(output.contains("Claiming a draw by threefold repetition"));
+    }
+
+    #[test]
+    fn cli_player_commands() {
+        let mut game = Game::new(Box::new(cli_player("e4\nundo\nd4\ndraw\nc4\n")), Box::new(cli_player("e5\nd5\ny\n")));
+        for _ in 0..6 {
+            game.step().unwrap();
+        }
+
+        let history: Vec<String> = game.move_history().iter().map(|move_| move_.to_string()).collect();
+        assert_eq!(history, ["d2d4", "d7d5", "c2c4"]);
+        assert_eq!(game.get_game_result(), Some(GameResult::Draw));
+
+        // A closed input resigns
+        let mut game = Game::new(Box::new(cli_player("")), Box::new(RandomPlayer));
+        game.step().unwrap();
+        assert_eq!(game.get_game_result(), Some(GameResult::BlackWin));
+    }
+}
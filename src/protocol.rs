@@ -0,0 +1,70 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::search::Searcher;
+
+// What the UCI and CECP front-ends share: a search on a thread of its own and the output that
+// it and the command loop both write to.
+
+// How often a search that is asked to stop is looked at until its thread has ended
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+// A search running on its own thread, which hands the searcher back together with its result
+pub(crate) struct RunningSearch<T> {
+    handle: JoinHandle<(Searcher, T)>,
+    stop: Arc<AtomicBool>,
+    // Without any limit it only ends when stopped
+    unlimited: bool,
+}
+
+impl<T: Send + 'static> RunningSearch<T> {
+    // Runs `work` with the searcher on a new thread. Write errors in `work` mean the GUI has
+    // gone away; they are ignored so that the searcher is still handed back.
+    pub(crate) fn spawn(
+        mut searcher: Searcher,
+        unlimited: bool,
+        work: impl FnOnce(&mut Searcher) -> T + Send + 'static,
+    ) -> Self {
+        let stop = searcher.stop_flag();
+        let handle = thread::spawn(move || {
+            let result = work(&mut searcher);
+            (searcher, result)
+        });
+        Self { handle, stop, unlimited }
+    }
+
+    pub(crate) fn unlimited(&self) -> bool {
+        self.unlimited
+    }
+
+    // Ends the search and waits for it. The flag is raised for as long as the thread runs, as
+    // a search lowers it when it starts and may not have started yet.
+    pub(crate) fn stop(self) -> (Searcher, T) {
+        while !self.handle.is_finished() {
+            self.stop.store(true, Ordering::Relaxed);
+            thread::sleep(STOP_POLL_INTERVAL);
+        }
+        self.join()
+    }
+
+    pub(crate) fn join(self) -> (Searcher, T) {
+        self.handle.join().expect("the search thread panicked")
+    }
+}
+
+pub(crate) fn send<W: Write>(output: &Mutex<W>, line: &str) -> io::Result<()> {
+    let mut output = output.lock().unwrap_or_else(PoisonError::into_inner);
+    writeln!(output, "{}", line)?;
+    output.flush()
+}
+
+// Gives back the output once no search thread holds it any more
+pub(crate) fn into_inner<W>(output: Arc<Mutex<W>>) -> W {
+    match Arc::try_unwrap(output) {
+        Ok(output) => output.into_inner().unwrap_or_else(PoisonError::into_inner),
+        Err(_) => unreachable!("the search thread has finished"),
+    }
+}
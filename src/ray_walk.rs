@@ -0,0 +1,238 @@
+// The ray walk that generated slider moves and attacks before the magic bitboard tables in
+// magic.rs. It shares no code with them, so the tests keep it as a reference to check the
+// lookups against, and MoveValidator can be switched back to it to time the two.
+
+use std::cell::Cell;
+
+thread_local! {
+    static IN_USE: Cell<bool> = const { Cell::new(false) };
+}
+
+// Whether MoveValidator answers slider attacks with the ray walk on this thread
+pub(crate) fn in_use() -> bool {
+    IN_USE.with(Cell::get)
+}
+
+// Runs `work` with MoveValidator using the ray walk instead of the magic lookups
+pub(crate) fn with_ray_walk<T>(work: impl FnOnce() -> T) -> T {
+    IN_USE.with(|in_use| in_use.set(true));
+    let result = work();
+    IN_USE.with(|in_use| in_use.set(false));
+    result
+}
+
+// North rays (upward movement)
+const NORTH_RAYS: [u64; 64] = {
+    let mut rays = [0u64; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut ray = 0u64;
+        let mut bit = 1u64 << sq;
+        let mut rank = sq / 8;
+        while rank < 7 {
+            bit <<= 8; // Move up one rank
+            ray |= bit;
+            rank += 1;
+        }
+        rays[sq] = ray;
+        sq += 1;
+    }
+    rays
+};
+
+// South rays (downward movement)
+const SOUTH_RAYS: [u64; 64] = {
+    let mut rays = [0u64; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut ray = 0u64;
+        let mut bit = 1u64 << sq;
+        let mut rank = sq / 8;
+        while rank > 0 {
+            bit >>= 8; // Move down one rank
+            ray |= bit;
+            rank -= 1;
+        }
+        rays[sq] = ray;
+        sq += 1;
+    }
+    rays
+};
+
+// East rays (rightward movement)
+const EAST_RAYS: [u64; 64] = {
+    let mut rays = [0u64; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut ray = 0u64;
+        let mut bit = 1u64 << sq;
+        let mut file = sq % 8;
+        while file < 7 { // Stop at the H file
+            bit <<= 1; // Move right one file
+            ray |= bit;
+            file += 1;
+        }
+        rays[sq] = ray;
+        sq += 1;
+    }
+    rays
+};
+
+// West rays (leftward movement)
+const WEST_RAYS: [u64; 64] = {
+    let mut rays = [0u64; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut ray = 0u64;
+        let mut bit = 1u64 << sq;
+        let mut file = sq % 8;
+        while file > 0 { // Stop at the A file
+            bit >>= 1; // Move left one file
+            ray |= bit;
+            file -= 1;
+        }
+        rays[sq] = ray;
+        sq += 1;
+    }
+    rays
+};
+
+// Northeast rays (up-right movement)
+const NORTH_EAST_RAYS: [u64; 64] = {
+    let mut rays = [0u64; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut ray = 0u64;
+        let mut bit = 1u64 << sq;
+        let file = sq % 8;
+        let mut rank_file = (sq / 8, file);
+
+        while rank_file.0 < 7 && rank_file.1 < 7 {
+            bit <<= 9; // Move up one rank and one file right
+            ray |= bit;
+            rank_file = (rank_file.0 + 1, rank_file.1 + 1);
+        }
+        rays[sq] = ray;
+        sq += 1;
+    }
+    rays
+};
+
+// Northwest rays (up-left movement)
+const NORTH_WEST_RAYS: [u64; 64] = {
+    let mut rays = [0u64; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut ray = 0u64;
+        let mut bit = 1u64 << sq;
+        let file = sq % 8;
+        let mut rank_file = (sq / 8, file);
+
+        while rank_file.0 < 7 && rank_file.1 > 0 {
+            bit <<= 7; // Move up one rank and one file left
+            ray |= bit;
+            rank_file = (rank_file.0 + 1, rank_file.1 - 1);
+        }
+        rays[sq] = ray;
+        sq += 1;
+    }
+    rays
+};
+
+// Southeast rays (down-right movement)
+const SOUTH_EAST_RAYS: [u64; 64] = {
+    let mut rays = [0u64; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut ray = 0u64;
+        let mut bit = 1u64 << sq;
+        let file = sq % 8;
+        let mut rank_file = (sq / 8, file);
+
+        while rank_file.0 > 0 && rank_file.1 < 7 {
+            bit >>= 7; // Move down one rank and one file right
+            ray |= bit;
+            rank_file = (rank_file.0 - 1, rank_file.1 + 1);
+        }
+        rays[sq] = ray;
+        sq += 1;
+    }
+    rays
+};
+
+// Southwest rays (down-left movement)
+const SOUTH_WEST_RAYS: [u64; 64] = {
+    let mut rays = [0u64; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut ray = 0u64;
+        let mut bit = 1u64 << sq;
+        let file = sq % 8;
+        let mut rank_file = (sq / 8, file);
+
+        while rank_file.0 > 0 && rank_file.1 > 0 {
+            bit >>= 9; // Move down one rank and one file left
+            ray |= bit;
+            rank_file = (rank_file.0 - 1, rank_file.1 - 1);
+        }
+        rays[sq] = ray;
+        sq += 1;
+    }
+    rays
+};
+
+// Squares attacked by a rook on the given square
+pub(crate) fn rook_attacks(square: u8, all_pieces: u64) -> u64 {
+    let sq = square as usize;
+    ray_attacks(NORTH_RAYS[sq], all_pieces, square)
+        | ray_attacks(SOUTH_RAYS[sq], all_pieces, square)
+        | ray_attacks(EAST_RAYS[sq], all_pieces, square)
+        | ray_attacks(WEST_RAYS[sq], all_pieces, square)
+}
+
+// Squares attacked by a bishop on the given square
+pub(crate) fn bishop_attacks(square: u8, all_pieces: u64) -> u64 {
+    let sq = square as usize;
+    ray_attacks(NORTH_EAST_RAYS[sq], all_pieces, square)
+        | ray_attacks(NORTH_WEST_RAYS[sq], all_pieces, square)
+        | ray_attacks(SOUTH_EAST_RAYS[sq], all_pieces, square)
+        | ray_attacks(SOUTH_WEST_RAYS[sq], all_pieces, square)
+}
+
+// The squares along a ray up to and including the first blocker
+fn ray_attacks(ray: u64, all_pieces: u64, from: u8) -> u64 {
+    let blockers = ray & all_pieces;
+
+    if blockers == 0 {
+        return ray;
+    }
+
+    let first_blocker = 1u64 << find_first_blocker_index(ray, blockers, from);
+    ray & (squares_before_blocker(first_blocker, from) | first_blocker)
+}
+
+// The squares between a piece and the first blocker on a ray
+fn squares_before_blocker(first_blocker: u64, from: u8) -> u64 {
+    if first_blocker > 1u64 << from {
+        // Ray direction is towards higher indices: everything below the blocker
+        first_blocker - 1
+    } else {
+        // Ray direction is towards lower indices: everything above the blocker
+        !(first_blocker | (first_blocker - 1))
+    }
+}
+
+// The index of the first blocker on a ray
+fn find_first_blocker_index(ray: u64, blockers: u64, reference_sq: u8) -> u32 {
+    let ray_with_blockers = ray & blockers;
+
+    // All moves northward go to higher square indices (e.g., from 10 -> 18, 26, etc.)
+    // whereas southward moves go to lower indices (e.g., from 55 -> 47, 39, etc.)
+    if reference_sq < ray_with_blockers.trailing_zeros() as u8 {
+        // Ray direction is towards higher indices (e.g., North or East)
+        ray_with_blockers.trailing_zeros()
+    } else {
+        // Ray direction is towards lower indices (e.g., South or West)
+        63 - ray_with_blockers.leading_zeros()
+    }
+}
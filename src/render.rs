@@ -0,0 +1,230 @@
+use std::fmt;
+
+use crate::board::Board;
+use crate::move_::Move;
+use crate::types::{Color, PieceType};
+
+const RESET: &str = "\x1b[0m";
+const LIGHT_SQUARE: &str = "\x1b[48;5;180m";
+const DARK_SQUARE: &str = "\x1b[48;5;137m";
+const LAST_MOVE_SQUARE: &str = "\x1b[48;5;143m";
+const CHECK_SQUARE: &str = "\x1b[48;5;167m";
+const WHITE_PIECE: &str = "\x1b[1;97m";
+const BLACK_PIECE: &str = "\x1b[1;30m";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceStyle {
+    // FEN letters, uppercase for white
+    Ascii,
+    Unicode,
+}
+
+// Draws a board as text, one line per rank. Highlights need ANSI colors to show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardRenderer {
+    style: PieceStyle,
+    labels: bool,
+    perspective: Color,
+    ansi_colors: bool,
+    last_move: Option<Move>,
+    highlight_check: bool,
+}
+
+impl BoardRenderer {
+    pub fn new() -> Self {
+        Self {
+            style: PieceStyle::Ascii,
+            labels: true,
+            perspective: Color::White,
+            ansi_colors: false,
+            last_move: None,
+            highlight_check: true,
+        }
+    }
+
+    pub fn style(mut self, style: PieceStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    // Rank numbers on the left and file letters below the board
+    pub fn labels(mut self, labels: bool) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    // The side drawn at the bottom
+    pub fn perspective(mut self, perspective: Color) -> Self {
+        self.perspective = perspective;
+        self
+    }
+
+    pub fn ansi_colors(mut self, ansi_colors: bool) -> Self {
+        self.ansi_colors = ansi_colors;
+        self
+    }
+
+    pub fn last_move(mut self, last_move: Option<Move>) -> Self {
+        self.last_move = last_move;
+        self
+    }
+
+    // Marks the square of a king in check
+    pub fn highlight_check(mut self, highlight_check: bool) -> Self {
+        self.highlight_check = highlight_check;
+        self
+    }
+
+    pub fn render(&self, board: &Board) -> String {
+        let mut lines = Vec::new();
+
+        let mut checked_kings = 0u64;
+        if self.highlight_check {
+            for color in [Color::White, Color::Black] {
+                if board.in_check(color) {
+                    checked_kings |= board.pieces(color, PieceType::King);
+                }
+            }
+        }
+
+        for row in 0..8u8 {
+            let rank = match self.perspective {
+                Color::White => 7 - row,
+                Color::Black => row,
+            };
+            let mut line = String::new();
+            if self.labels {
+                line.push_str(&format!("{} ", rank + 1));
+            }
+
+            for column in 0..8u8 {
+                let file = match self.perspective {
+                    Color::White => column,
+                    Color::Black => 7 - column,
+                };
+                let square = rank * 8 + file;
+                let piece = board.piece_at(square);
+
+                if self.ansi_colors {
+                    let background = if checked_kings & (1u64 << square) != 0 {
+                        CHECK_SQUARE
+                    } else if self.last_move.is_some_and(|move_| move_.from() == square || move_.to() == square) {
+                        LAST_MOVE_SQUARE
+                    } else if (rank + file) % 2 == 0 {
+                        DARK_SQUARE
+                    } else {
+                        LIGHT_SQUARE
+                    };
+                    let foreground = match piece {
+                        Some((Color::White, _)) => WHITE_PIECE,
+                        _ => BLACK_PIECE,
+                    };
+                    let symbol = piece.map_or(' ', |(color, piece_type)| self.symbol(color, piece_type));
+                    line.push_str(&format!("{}{} {} ", background, foreground, symbol));
+                } else {
+                    let symbol = piece.map_or('.', |(color, piece_type)| self.symbol(color, piece_type));
+                    line.push(' ');
+                    line.push(symbol);
+                }
+            }
+
+            if self.ansi_colors {
+                line.push_str(RESET);
+            }
+            lines.push(line);
+        }
+
+        if self.labels {
+            let files = (0..8u8).map(|column| {
+                let file = match self.perspective {
+                    Color::White => column,
+                    Color::Black => 7 - column,
+                };
+                (b'a' + file) as char
+            });
+            // File letters line up with the pieces, which are padded to three columns with colors
+            let footer: String = if self.ansi_colors {
+                files.map(|file| format!(" {} ", file)).collect()
+            } else {
+                files.map(|file| format!(" {}", file)).collect()
+            };
+            lines.push(format!("  {}", footer.trim_end()));
+        }
+
+        lines.join("\n")
+    }
+
+    fn symbol(&self, color: Color, piece_type: PieceType) -> char {
+        match self.style {
+            PieceStyle::Ascii if color == Color::White => piece_type.to_char().to_ascii_uppercase(),
+            PieceStyle::Ascii => piece_type.to_char(),
+            PieceStyle::Unicode => match (color, piece_type) {
+                (Color::White, PieceType::King) => '♔',
+                (Color::White, PieceType::Queen) => '♕',
+                (Color::White, PieceType::Rook) => '♖',
+                (Color::White, PieceType::Bishop) => '♗',
+                (Color::White, PieceType::Knight) => '♘',
+                (Color::White, PieceType::Pawn) => '♙',
+                (Color::Black, PieceType::King) => '♚',
+                (Color::Black, PieceType::Queen) => '♛',
+                (Color::Black, PieceType::Rook) => '♜',
+                (Color::Black, PieceType::Bishop) => '♝',
+                (Color::Black, PieceType::Knight) => '♞',
+                (Color::Black, PieceType::Pawn) => '♟',
+            },
+        }
+    }
+}
+
+impl Default for BoardRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// An ASCII diagram from white's side, with labels
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&BoardRenderer::new().render(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_ascii_boards() {
+        let expected = "\
+8  r n b q k b n r
+7  p p p p p p p p
+6  . . . . . . . .
+5  . . . . . . . .
+4  . . . . . . . .
+3  . . . . . . . .
+2  P P P P P P P P
+1  R N B Q K B N R
+   a b c d e f g h";
+        assert_eq!(Board::new().to_string(), expected);
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let flipped = BoardRenderer::new().perspective(Color::Black).labels(false).render(&board);
+        assert_eq!(flipped.lines().next(), Some(" . . . K . . . R"));
+        assert_eq!(flipped.lines().count(), 8);
+    }
+
+    #[test]
+    fn draws_unicode_and_highlights() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+
+        let unicode = BoardRenderer::new().style(PieceStyle::Unicode).render(&board);
+        assert!(unicode.starts_with("8  . . . . ♚ . . ."));
+
+        let last_move = Move::new(0, 4, None, false);
+        let colored = BoardRenderer::new().ansi_colors(true).last_move(Some(last_move)).render(&board);
+        assert!(colored.contains(&format!("{}{} k ", CHECK_SQUARE, BLACK_PIECE)));
+        assert!(colored.contains(&format!("{}{} R ", LAST_MOVE_SQUARE, WHITE_PIECE)));
+        assert!(colored.contains(&format!("{}{}   ", LAST_MOVE_SQUARE, BLACK_PIECE)));
+        assert!(colored.lines().all(|line| !line.contains('\x1b') || line.ends_with(RESET)));
+    }
+}
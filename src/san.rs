@@ -0,0 +1,236 @@
+use crate::board::Board;
+use crate::error::NotationError;
+use crate::move_::Move;
+use crate::move_validator::MoveValidator;
+use crate::types::{algebraic_to_square, square_to_algebraic, Color, PieceType};
+
+impl Move {
+    // Standard Algebraic Notation of this move in the given position, e.g. "Nbd7", "exd6",
+    // "e8=Q+" or "O-O-O#". The move is expected to be legal on the board.
+    pub fn to_san(self, board: &Board) -> String {
+        let Some((color, piece_type)) = board.piece_at(self.from()) else {
+            return self.to_string();
+        };
+
+        let move_validator = MoveValidator::new();
+        let mut san = String::new();
+
+        if self.is_castling() {
+            san.push_str(if self.to() % 8 == 6 { "O-O" } else { "O-O-O" });
+        } else {
+            let is_capture = board.piece_at(self.to()).is_some()
+                || (piece_type == PieceType::Pawn && self.from() % 8 != self.to() % 8);
+
+            if piece_type == PieceType::Pawn {
+                if is_capture {
+                    san.push(file_char(self.from()));
+                }
+            } else {
+                san.push(piece_type.to_char().to_ascii_uppercase());
+
+                // Other pieces of the same kind that can reach the destination
+                let rivals: Vec<Move> = move_validator
+                    .get_legal_moves(board, color)
+                    .into_iter()
+                    .filter(|other| {
+                        other.to() == self.to()
+                            && other.from() != self.from()
+                            && board.piece_at(other.from()) == Some((color, piece_type))
+                    })
+                    .collect();
+
+                if !rivals.is_empty() {
+                    let same_file = rivals.iter().any(|other| other.from() % 8 == self.from() % 8);
+                    let same_rank = rivals.iter().any(|other| other.from() / 8 == self.from() / 8);
+
+                    if !same_file {
+                        san.push(file_char(self.from()));
+                    } else if !same_rank {
+                        san.push(rank_char(self.from()));
+                    } else {
+                        san.push_str(&square_to_algebraic(self.from()));
+                    }
+                }
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+
+            san.push_str(&square_to_algebraic(self.to()));
+
+            if let Some(promotion) = self.promotion() {
+                san.push('=');
+                san.push(promotion.to_char().to_ascii_uppercase());
+            }
+        }
+
+        let mut after = board.clone();
+        after.make_move(&self);
+
+        if after.in_check(color.opponent()) {
+            let is_mate = move_validator.get_legal_moves(&after, color.opponent()).is_empty();
+            san.push(if is_mate { '#' } else { '+' });
+        }
+
+        san
+    }
+
+    // Parses a SAN move for the given side, tolerating missing or superfluous capture marks,
+    // check suffixes, annotations, over-disambiguation, "0-0" castling, promotions without
+    // '=' and trailing "e.p.".
+    pub fn from_san(san: &str, board: &Board, color: Color) -> Result<Self, NotationError> {
+        let legal_moves = MoveValidator::new().get_legal_moves(board, color);
+
+        let mut text = san.trim();
+        text = text.trim_end_matches(['+', '#', '!', '?']);
+        text = text.strip_suffix("e.p.").or_else(|| text.strip_suffix("ep")).unwrap_or(text).trim_end();
+        text = text.trim_end_matches(['+', '#']);
+
+        if text.is_empty() {
+            return Err(NotationError::Malformed { notation: san.to_string(), reason: "empty move" });
+        }
+
+        // Castling, written with letter O or digit zero
+        let castling = text.replace('0', "O").to_ascii_uppercase();
+        if castling == "O-O" || castling == "O-O-O" {
+            let kingside = castling == "O-O";
+            return legal_moves
+                .into_iter()
+                .find(|move_| move_.is_castling() && (move_.to() % 8 == 6) == kingside)
+                .ok_or_else(|| NotationError::Illegal { notation: san.to_string(), color });
+        }
+
+        let mut chars: Vec<char> = text.chars().filter(|&c| c != 'x' && c != ':' && c != '-').collect();
+
+        // Leading uppercase piece letter; pawn moves have none
+        let piece_type = match chars.first() {
+            Some(&c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => {
+                chars.remove(0);
+                PieceType::from_char(c).unwrap()
+            }
+            _ => PieceType::Pawn,
+        };
+
+        // Trailing promotion piece, with or without '='
+        let mut promotion = None;
+        if piece_type == PieceType::Pawn && chars.len() >= 3 {
+            let last = chars[chars.len() - 1];
+            if let Some(promoted @ (PieceType::Knight | PieceType::Bishop | PieceType::Rook | PieceType::Queen)) =
+                PieceType::from_char(last).filter(|_| chars[chars.len() - 2] == '=' || chars[chars.len() - 2].is_ascii_digit())
+            {
+                promotion = Some(promoted);
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+            }
+        }
+
+        if chars.len() < 2 {
+            return Err(NotationError::Malformed { notation: san.to_string(), reason: "missing destination square" });
+        }
+
+        let destination: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = algebraic_to_square(&destination)
+            .ok_or_else(|| NotationError::Malformed { notation: san.to_string(), reason: "invalid destination square" })?;
+
+        // Whatever remains disambiguates the origin by file, rank or both
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' => from_file = Some(c as u8 - b'a'),
+                '1'..='8' => from_rank = Some(c as u8 - b'1'),
+                _ => {
+                    return Err(NotationError::Malformed { notation: san.to_string(), reason: "unexpected character" });
+                }
+            }
+        }
+
+        let candidates: Vec<Move> = legal_moves
+            .into_iter()
+            .filter(|move_| {
+                move_.to() == to
+                    && !move_.is_castling()
+                    && move_.promotion() == promotion
+                    && board.piece_at(move_.from()) == Some((color, piece_type))
+                    && from_file.is_none_or(|file| move_.from() % 8 == file)
+                    && from_rank.is_none_or(|rank| move_.from() / 8 == rank)
+            })
+            .collect();
+
+        match candidates.as_slice() {
+            [move_] => Ok(*move_),
+            [] => Err(NotationError::Illegal { notation: san.to_string(), color }),
+            _ => Err(NotationError::Ambiguous {
+                notation: san.to_string(),
+                candidates: candidates.iter().map(|move_| move_.to_san(board)).collect(),
+            }),
+        }
+    }
+}
+
+fn file_char(square: u8) -> char {
+    (b'a' + square % 8) as char
+}
+
+fn rank_char(square: u8) -> char {
+    (b'1' + square / 8) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn san_of(fen: &str, color: Color, uci: &str) -> String {
+        let board = Board::from_fen(fen).unwrap();
+        Move::from_uci(uci, &board, color).unwrap().to_san(&board)
+    }
+
+    #[test]
+    fn writes_san() {
+        assert_eq!(san_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Color::White, "g1f3"), "Nf3");
+        assert_eq!(san_of("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4", Color::White, "h5f7"), "Qxf7#");
+        assert_eq!(san_of("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", Color::White, "e1c1"), "O-O-O");
+        assert_eq!(san_of("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", Color::Black, "e8g8"), "O-O");
+        assert_eq!(san_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", Color::White, "e5d6"), "exd6");
+        assert_eq!(san_of("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1", Color::White, "a7b8q"), "axb8=Q+");
+        assert_eq!(san_of("r4rk1/8/8/8/8/8/8/4K3 b - - 0 1", Color::Black, "a8d8"), "Rad8");
+        assert_eq!(san_of("4k3/8/8/8/R7/8/8/R3K3 w - - 0 1", Color::White, "a1a2"), "R1a2");
+        assert_eq!(san_of("k7/8/8/8/8/2Q1Q3/8/2Q1K3 w - - 0 1", Color::White, "c3d2"), "Qc3d2");
+    }
+
+    #[test]
+    fn reads_tolerant_san() {
+        let board = Board::from_fen("r1bqkb1r/ppp2ppp/2n2n2/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 4").unwrap();
+
+        for (san, uci) in [
+            ("exd6", "e5d6"),
+            ("exd6e.p.", "e5d6"),
+            ("ed6", "e5d6"),
+            ("e5xd6 e.p.", "e5d6"),
+            ("Nf3", "g1f3"),
+            ("Ng1f3", "g1f3"),
+            ("Ng1-f3!?", "g1f3"),
+        ] {
+            assert_eq!(Move::from_san(san, &board, Color::White).unwrap().to_string(), uci, "{}", san);
+        }
+
+        let board = Board::from_fen("rnbqkb1r/ppp2ppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(Move::from_san("Nbd7", &board, Color::Black).unwrap().to_string(), "b8d7");
+        assert!(matches!(
+            Move::from_san("Nd7", &board, Color::Black),
+            Err(NotationError::Ambiguous { candidates, .. }) if candidates == ["Nfd7", "Nbd7"]
+        ));
+
+        let board = Board::from_fen("r3k2r/pppn1ppp/5n2/8/8/8/PPPPPPPP/R3K2R b KQkq - 0 1").unwrap();
+        assert_eq!(Move::from_san("0-0-0", &board, Color::Black).unwrap().to_string(), "e8c8");
+        assert_eq!(Move::from_san("O-O+", &board, Color::Black).unwrap().to_string(), "e8g8");
+
+        let board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(Move::from_san("a8=N", &board, Color::White).unwrap().to_string(), "a7a8n");
+        assert_eq!(Move::from_san("a8Q", &board, Color::White).unwrap().to_string(), "a7a8q");
+        assert!(Move::from_san("a8", &board, Color::White).is_err());
+    }
+}
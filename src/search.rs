@@ -0,0 +1,571 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::board::Board;
+use crate::evaluation::evaluate;
+use crate::move_::Move;
+use crate::move_validator::MoveValidator;
+use crate::transposition::{Bound, TranspositionTable};
+use crate::types::{Color, PieceType};
+
+// Score of being mated at the root; mates further away score closer to zero by one per ply
+pub const MATE_SCORE: i32 = 30_000;
+const INFINITY: i32 = 32_000;
+pub(crate) const MAX_PLY: usize = 128;
+
+// How often, in nodes, the clock and the stop flag are looked at
+const CHECK_INTERVAL: u64 = 1024;
+
+// When a search must stop. Without any limit it runs until its stop flag is raised.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    depth: Option<u32>,
+    nodes: Option<u64>,
+    time: Option<Duration>,
+    multi_pv: Option<usize>,
+}
+
+impl SearchLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn nodes(mut self, nodes: u64) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    pub fn time(mut self, time: Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    // How many of the best root moves get their own principal variation, one by default
+    pub fn multi_pv(mut self, lines: usize) -> Self {
+        self.multi_pv = Some(lines.max(1));
+        self
+    }
+
+    pub fn get_depth(&self) -> Option<u32> {
+        self.depth
+    }
+
+    pub fn get_nodes(&self) -> Option<u64> {
+        self.nodes
+    }
+
+    pub fn get_time(&self) -> Option<Duration> {
+        self.time
+    }
+
+    pub fn get_multi_pv(&self) -> usize {
+        self.multi_pv.unwrap_or(1)
+    }
+}
+
+// The outcome of one completed iteration of the search
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchInfo {
+    depth: u32,
+    score: i32,
+    nodes: u64,
+    elapsed: Duration,
+    pv: Vec<Move>,
+    multi_pv: usize,
+    hashfull: u32,
+}
+
+impl SearchInfo {
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    // In centipawns from the side to move's point of view, see MATE_SCORE for mates
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+    // Moves until mate, negative when the side to move is getting mated
+    pub fn mate_in(&self) -> Option<i32> {
+        let plies = MATE_SCORE - self.score.abs();
+        if plies > MAX_PLY as i32 {
+            None
+        } else if self.score > 0 {
+            Some((plies + 1) / 2)
+        } else {
+            Some(-(plies + 1) / 2)
+        }
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    // The principal variation, starting with the best move
+    pub fn pv(&self) -> &[Move] {
+        &self.pv
+    }
+
+    pub fn best_move(&self) -> Move {
+        self.pv[0]
+    }
+
+    // The rank of this variation among those searched, 1 for the best, see SearchLimits::multi_pv
+    pub fn multi_pv(&self) -> usize {
+        self.multi_pv
+    }
+
+    // Permille of the transposition table filled by this search
+    pub fn hashfull(&self) -> u32 {
+        self.hashfull
+    }
+}
+
+// Negamax with alpha-beta pruning, iterative deepening, a transposition table and a
+// quiescence search on captures. The table is kept between searches.
+pub struct Searcher {
+    move_validator: MoveValidator,
+    table: TranspositionTable,
+    stop: Arc<AtomicBool>,
+    limits: SearchLimits,
+    started: Instant,
+    nodes: u64,
+    stopped: bool,
+    // Keys of the positions leading to the current node, for repetition detection
+    path: Vec<u64>,
+    // Triangular principal variation table and the variation of the last iteration
+    pv: Vec<Vec<Move>>,
+    previous_pv: Vec<Move>,
+    // Root moves already given a variation of their own in this iteration
+    excluded_root_moves: Vec<Move>,
+}
+
+impl Searcher {
+    pub fn new() -> Self {
+        Self {
+            move_validator: MoveValidator::new(),
+            table: TranspositionTable::default(),
+            stop: Arc::new(AtomicBool::new(false)),
+            limits: SearchLimits::new(),
+            started: Instant::now(),
+            nodes: 0,
+            stopped: false,
+            path: Vec::new(),
+            pv: vec![Vec::new(); MAX_PLY + 1],
+            previous_pv: Vec::new(),
+            excluded_root_moves: Vec::new(),
+        }
+    }
+
+    // Replaces the transposition table with an empty one of at most `megabytes`
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.table.resize(megabytes);
+    }
+
+    // Forgets every earlier search, e.g. before a new game
+    pub fn clear_hash(&mut self) {
+        self.table.clear();
+    }
+
+    // Raising the flag from another thread ends the search in progress. Every search lowers the
+    // flag when it starts, so it has no effect on a search that has not started yet.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    // Searches the position with `turn` to move. `history` holds the keys of the positions
+    // played before it so that repetitions are scored as draws. `on_iteration` is told about
+    // every variation of every completed depth. Returns the best variation, or None when there
+    // is no legal move.
+    pub fn search(
+        &mut self,
+        board: &Board,
+        turn: Color,
+        history: &[u64],
+        limits: SearchLimits,
+        mut on_iteration: impl FnMut(&SearchInfo),
+    ) -> Option<SearchInfo> {
+        self.stop.store(false, Ordering::Relaxed);
+        let root_moves = self.move_validator.get_legal_moves(board, turn);
+        if root_moves.is_empty() {
+            return None;
+        }
+
+        self.limits = limits;
+        self.started = Instant::now();
+        self.nodes = 0;
+        self.stopped = false;
+        self.path = history.to_vec();
+        self.previous_pv.clear();
+        self.table.new_search();
+
+        let mut board = board.clone();
+        let mut best: Option<SearchInfo> = None;
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32).clamp(1, MAX_PLY as u32);
+
+        let lines = limits.get_multi_pv().min(root_moves.len());
+
+        'deepening: for depth in 1..=max_depth {
+            self.excluded_root_moves.clear();
+
+            for multi_pv in 1..=lines {
+                let score = self.negamax(&mut board, turn, depth, 0, -INFINITY, INFINITY);
+
+                // An interrupted search is only trusted for its first move, and only without anything better
+                if self.stopped && (best.is_some() || multi_pv > 1) {
+                    break 'deepening;
+                }
+
+                let mut pv = self.pv[0].clone();
+                if pv.is_empty() {
+                    let unsearched = root_moves.iter().find(|move_| !self.excluded_root_moves.contains(move_));
+                    pv.extend(unsearched);
+                }
+                let info = SearchInfo {
+                    depth,
+                    score,
+                    nodes: self.nodes,
+                    elapsed: self.started.elapsed(),
+                    pv,
+                    multi_pv,
+                    hashfull: self.table.hashfull(),
+                };
+                on_iteration(&info);
+                self.excluded_root_moves.push(info.best_move());
+
+                if multi_pv == 1 {
+                    self.previous_pv = info.pv.clone();
+                    best = Some(info);
+                }
+                if self.stopped {
+                    break 'deepening;
+                }
+            }
+
+            let score = best.as_ref().map_or(0, SearchInfo::score);
+            if MATE_SCORE - score.abs() <= depth as i32 {
+                break;
+            }
+
+            // The next iteration takes several times longer and would most likely be cut short
+            if limits.time.is_some_and(|time| self.started.elapsed() > time / 2) {
+                break;
+            }
+        }
+
+        best
+    }
+
+    fn negamax(&mut self, board: &mut Board, turn: Color, depth: u32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv[ply].clear();
+
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if ply > 0 && (self.is_repetition(board.hash()) || board.has_insufficient_material()) {
+            return 0;
+        }
+
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(board, turn, ply, alpha, beta);
+        }
+
+        // Scores that would not change the principal variation are taken from the table;
+        // exact scores inside the window still have to be searched to extend it
+        let entry = self.table.probe(board.hash());
+        if let Some(entry) = entry
+            && ply > 0
+            && entry.depth() >= depth
+        {
+            let score = entry.score(ply);
+            let usable = match entry.bound() {
+                Bound::Exact => score <= alpha || score >= beta,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if usable {
+                return score;
+            }
+        }
+
+        let mut moves = self.move_validator.get_legal_moves(board, turn);
+        if moves.is_empty() {
+            return if board.in_check(turn) { -(MATE_SCORE - ply as i32) } else { 0 };
+        }
+
+        // Mate distance pruning: no line from here beats a mate found closer to the root
+        let alpha_bound = alpha.max(-(MATE_SCORE - ply as i32));
+        let beta_bound = beta.min(MATE_SCORE - ply as i32 - 1);
+        if alpha_bound >= beta_bound {
+            return alpha_bound;
+        }
+        alpha = alpha_bound;
+        let original_alpha = alpha;
+        let mut best_move = None;
+
+        self.order_moves(board, &mut moves, ply, entry.and_then(|entry| entry.best_move()));
+
+        if ply == 0 {
+            moves.retain(|move_| !self.excluded_root_moves.contains(move_));
+        }
+
+        self.path.push(board.hash());
+        for move_ in moves {
+            let undo = board.make_move(&move_);
+            let score = -self.negamax(board, turn.opponent(), depth - 1, ply + 1, -beta_bound, -alpha);
+            board.unmake_move(&move_, &undo);
+
+            if self.stopped {
+                break;
+            }
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(move_);
+                self.update_pv(ply, move_);
+
+                if alpha >= beta_bound {
+                    break;
+                }
+            }
+        }
+        self.path.pop();
+
+        // A root searched without some of its moves has no score of its own
+        if !self.stopped && (ply > 0 || self.excluded_root_moves.is_empty()) {
+            let bound = if alpha >= beta_bound {
+                Bound::Lower
+            } else if alpha > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            self.table.store(board.hash(), depth, bound, alpha, best_move, ply);
+        }
+
+        alpha
+    }
+
+    // Resolves captures so that the static evaluation is not taken in the middle of an exchange.
+    // In check every evasion is searched, as standing pat is not an option.
+    fn quiescence(&mut self, board: &mut Board, turn: Color, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv[ply].clear();
+
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if ply >= MAX_PLY {
+            return evaluate(board, turn);
+        }
+
+        let in_check = board.in_check(turn);
+        let mut moves = self.move_validator.get_legal_moves(board, turn);
+
+        if in_check {
+            if moves.is_empty() {
+                return -(MATE_SCORE - ply as i32);
+            }
+        } else {
+            let stand_pat = evaluate(board, turn);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            moves.retain(|move_| is_tactical(board, move_));
+        }
+
+        self.order_moves(board, &mut moves, ply, None);
+
+        for move_ in moves {
+            let undo = board.make_move(&move_);
+            let score = -self.quiescence(board, turn.opponent(), ply + 1, -beta, -alpha);
+            board.unmake_move(&move_, &undo);
+
+            if self.stopped {
+                break;
+            }
+
+            if score > alpha {
+                alpha = score;
+                self.update_pv(ply, move_);
+
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        alpha
+    }
+
+    // The best move stored in the table first, then the move of the last iteration's principal
+    // variation, then captures by most valuable victim and least valuable attacker, then the rest
+    fn order_moves(&self, board: &Board, moves: &mut [Move], ply: usize, hash_move: Option<Move>) {
+        let pv_move = self.previous_pv.get(ply).copied();
+
+        moves.sort_by_cached_key(|move_| {
+            if Some(*move_) == hash_move {
+                return i32::MIN;
+            }
+            if Some(*move_) == pv_move {
+                return i32::MIN + 1;
+            }
+
+            let attacker = board.piece_at(move_.from()).map_or(0, |(_, piece_type)| piece_value(piece_type));
+            let victim = match board.piece_at(move_.to()) {
+                Some((_, piece_type)) => piece_value(piece_type),
+                None if is_tactical(board, move_) && move_.promotion().is_none() => piece_value(PieceType::Pawn),
+                None => 0,
+            };
+            let promotion = move_.promotion().map_or(0, piece_value);
+
+            if victim == 0 && promotion == 0 {
+                0
+            } else {
+                -(victim * 10 + promotion - attacker / 10)
+            }
+        });
+    }
+
+    fn update_pv(&mut self, ply: usize, move_: Move) {
+        let (head, tail) = self.pv.split_at_mut(ply + 1);
+        head[ply].clear();
+        head[ply].push(move_);
+        head[ply].extend_from_slice(&tail[0]);
+    }
+
+    // Any earlier occurrence counts, as the side that repeated could repeat again
+    fn is_repetition(&self, hash: u64) -> bool {
+        self.path.iter().rev().skip(1).step_by(2).any(|&earlier| earlier == hash)
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+
+        if self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes) {
+            self.stopped = true;
+        } else if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.stopped = self.stop.load(Ordering::Relaxed)
+                || self.limits.time.is_some_and(|time| self.started.elapsed() >= time);
+        }
+
+        self.stopped
+    }
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// How long to think on a clock with `time_left`: a slice of it, about enough for thirty more
+// moves or for the moves until the next time control, plus most of the increment
+pub(crate) fn time_budget(time_left: Duration, increment: Duration, moves_to_go: Option<u32>) -> Duration {
+    let moves = moves_to_go.map_or(30, |moves| moves.clamp(1, 30));
+    (time_left / moves + increment * 3 / 4).min(time_left / 2)
+}
+
+// Captures, including en passant, and promotions
+fn is_tactical(board: &Board, move_: &Move) -> bool {
+    let is_en_passant = board.en_passant_square() == Some(move_.to())
+        && matches!(board.piece_at(move_.from()), Some((_, PieceType::Pawn)))
+        && move_.from() % 8 != move_.to() % 8;
+
+    board.piece_at(move_.to()).is_some() || is_en_passant || move_.promotion().is_some()
+}
+
+fn piece_value(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::Pawn => 100,
+        PieceType::Knight => 320,
+        PieceType::Bishop => 330,
+        PieceType::Rook => 500,
+        PieceType::Queen => 900,
+        PieceType::King => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(fen: &str, turn: Color, depth: u32) -> SearchInfo {
+        let board = Board::from_fen(fen).unwrap();
+        Searcher::new().search(&board, turn, &[], SearchLimits::new().depth(depth), |_| {}).unwrap()
+    }
+
+    #[test]
+    fn finds_mates() {
+        let info = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", Color::White, 3);
+        assert_eq!(info.best_move().to_string(), "a1a8");
+        assert_eq!(info.mate_in(), Some(1));
+
+        // Nf6+ gxf6 Bxf7#
+        let info = search("r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 0", Color::White, 4);
+        assert_eq!(info.best_move().to_string(), "d5f6");
+        assert_eq!(info.mate_in(), Some(2));
+
+        let info = search("7k/8/8/8/8/r7/1r6/6K1 w - - 0 1", Color::White, 3);
+        assert_eq!(info.mate_in(), Some(-1));
+    }
+
+    #[test]
+    fn wins_material_and_sees_recaptures() {
+        let info = search("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", Color::White, 2);
+        assert_eq!(info.best_move().to_string(), "d2d5");
+
+        // The pawn on d5 is defended, so taking it loses the queen
+        let info = search("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1", Color::White, 1);
+        assert_ne!(info.best_move().to_string(), "d1d5");
+        assert!(info.score() > 0);
+    }
+
+    #[test]
+    fn respects_limits() {
+        let board = Board::new();
+        let mut searcher = Searcher::new();
+        let mut depths = Vec::new();
+
+        let info = searcher.search(&board, Color::White, &[], SearchLimits::new().depth(3), |info| depths.push(info.depth()));
+        assert_eq!(depths, [1, 2, 3]);
+        assert_eq!(info.unwrap().pv().len(), 3);
+
+        let info = searcher.search(&board, Color::White, &[], SearchLimits::new().nodes(500), |_| {}).unwrap();
+        assert!(info.nodes() <= 500);
+
+        let stalemate = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(searcher.search(&stalemate, Color::Black, &[], SearchLimits::new().depth(1), |_| {}).is_none());
+    }
+
+    #[test]
+    fn reuses_the_transposition_table() {
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let mut searcher = Searcher::new();
+        let limits = SearchLimits::new().depth(3);
+
+        let first = searcher.search(&board, Color::White, &[], limits, |_| {}).unwrap();
+        let second = searcher.search(&board, Color::White, &[], limits, |_| {}).unwrap();
+        assert!(second.nodes() < first.nodes());
+
+        searcher.clear_hash();
+        let cleared = searcher.search(&board, Color::White, &[], limits, |_| {}).unwrap();
+        assert_eq!(cleared.nodes(), first.nodes());
+    }
+}
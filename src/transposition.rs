@@ -0,0 +1,190 @@
+use std::mem;
+
+use crate::move_::Move;
+
+pub const DEFAULT_HASH_MB: usize = 16;
+
+// Scores this close to MATE_SCORE are mates, stored relative to the node instead of the root
+const MATE_BOUND: i32 = crate::search::MATE_SCORE - crate::search::MAX_PLY as i32;
+
+// hashfull is sampled over this many slots, as UCI reports it in permille
+const HASHFULL_SAMPLE: usize = 1000;
+
+// How a stored score relates to the true score of the position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    // The search failed high: the true score is at least this
+    Lower,
+    // The search failed low: the true score is at most this
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TtEntry {
+    key: u64,
+    best_move: Option<Move>,
+    score: i32,
+    depth: u8,
+    bound: Bound,
+    age: u8,
+}
+
+impl TtEntry {
+    pub fn best_move(&self) -> Option<Move> {
+        self.best_move
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth as u32
+    }
+
+    pub fn bound(&self) -> Bound {
+        self.bound
+    }
+
+    // The score as seen from the root of a search, `ply` plies above this position
+    pub fn score(&self, ply: usize) -> i32 {
+        if self.score > MATE_BOUND {
+            self.score - ply as i32
+        } else if self.score < -MATE_BOUND {
+            self.score + ply as i32
+        } else {
+            self.score
+        }
+    }
+}
+
+// A fixed-size table of search results keyed by position hash, one entry per slot.
+// A new result replaces the old one unless that was searched deeper in the current search.
+#[derive(Clone, Debug)]
+pub struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+    age: u8,
+}
+
+impl TranspositionTable {
+    // Sized to use at most `megabytes` of memory, with at least one slot
+    pub fn new(megabytes: usize) -> Self {
+        let slots = (megabytes * 1024 * 1024 / mem::size_of::<Option<TtEntry>>()).max(1);
+        Self { entries: vec![None; slots], age: 0 }
+    }
+
+    pub fn resize(&mut self, megabytes: usize) {
+        *self = Self::new(megabytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+        self.age = 0;
+    }
+
+    // Called once per search, so that entries of earlier searches are replaced first
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    // `score` is as seen from the root of the search, `ply` plies above this position
+    pub fn store(&mut self, key: u64, depth: u32, bound: Bound, score: i32, best_move: Option<Move>, ply: usize) {
+        let index = self.index(key);
+        let slot = &mut self.entries[index];
+
+        if let Some(old) = slot
+            && old.age == self.age
+            && old.key != key
+            && old.depth as u32 > depth
+        {
+            return;
+        }
+
+        // Keep the best move of an earlier search of the position when this one failed low
+        let best_move = best_move.or_else(|| slot.filter(|old| old.key == key).and_then(|old| old.best_move));
+        let score = if score > MATE_BOUND {
+            score + ply as i32
+        } else if score < -MATE_BOUND {
+            score - ply as i32
+        } else {
+            score
+        };
+
+        *slot = Some(TtEntry { key, best_move, score, depth: depth.min(u8::MAX as u32) as u8, bound, age: self.age });
+    }
+
+    // Permille of the table used by the current search
+    pub fn hashfull(&self) -> u32 {
+        let sample = &self.entries[..self.entries.len().min(HASHFULL_SAMPLE)];
+        let used = sample.iter().flatten().filter(|entry| entry.age == self.age).count();
+        (used * 1000 / sample.len()) as u32
+    }
+
+    fn index(&self, key: u64) -> usize {
+        ((key as u128 * self.entries.len() as u128) >> 64) as usize
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::MATE_SCORE;
+
+    #[test]
+    fn stores_and_probes() {
+        let mut table = TranspositionTable::new(1);
+        let move_ = Move::new(12, 28, None, false);
+
+        assert_eq!(table.probe(42), None);
+        table.store(42, 5, Bound::Lower, 120, Some(move_), 3);
+        let entry = table.probe(42).unwrap();
+        assert_eq!((entry.depth(), entry.bound(), entry.score(7), entry.best_move()), (5, Bound::Lower, 120, Some(move_)));
+
+        // A fail low keeps the move found before
+        table.store(42, 6, Bound::Upper, -30, None, 3);
+        assert_eq!(table.probe(42).unwrap().best_move(), Some(move_));
+
+        table.clear();
+        assert_eq!(table.probe(42), None);
+    }
+
+    #[test]
+    fn adjusts_mate_scores_by_ply() {
+        let mut table = TranspositionTable::new(1);
+
+        // Mate in 3 plies from a node 2 plies into the search is mate in 1 ply from that node
+        table.store(7, 4, Bound::Exact, MATE_SCORE - 5, None, 2);
+        assert_eq!(table.probe(7).unwrap().score(2), MATE_SCORE - 5);
+        assert_eq!(table.probe(7).unwrap().score(6), MATE_SCORE - 9);
+
+        table.store(7, 4, Bound::Exact, -(MATE_SCORE - 5), None, 2);
+        assert_eq!(table.probe(7).unwrap().score(0), -(MATE_SCORE - 3));
+    }
+
+    #[test]
+    fn prefers_deep_and_recent_entries() {
+        // A single slot, so that every key collides
+        let mut table = TranspositionTable::new(0);
+
+        table.store(1, 8, Bound::Exact, 10, None, 0);
+        table.store(2, 3, Bound::Exact, 20, None, 0);
+        assert!(table.probe(1).is_some());
+        assert_eq!(table.probe(2), None);
+
+        table.new_search();
+        table.store(2, 3, Bound::Exact, 20, None, 0);
+        assert_eq!(table.probe(1), None);
+        assert_eq!(table.probe(2).unwrap().score(0), 20);
+        assert_eq!(table.hashfull(), 1000);
+
+        table.new_search();
+        assert_eq!(table.hashfull(), 0);
+    }
+}
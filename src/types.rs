@@ -1,11 +1,32 @@
-#[derive(Clone, Copy, PartialEq)]
-pub(crate) enum Color {
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
     White,
     Black,
 }
 
-#[derive(Clone, Copy)]
-pub(crate) enum PieceType {
+impl Color {
+    pub fn opponent(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Color::White => "White",
+            Color::Black => "Black",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PieceType {
     Pawn,
     Knight,
     Bishop,
@@ -14,29 +35,141 @@ pub(crate) enum PieceType {
     King,
 }
 
-#[derive(Copy, Clone)]
-enum Piece {
-    WhitePawn,
-    BlackPawn,
-    WhiteKnight,
-    BlackKnight,
-    WhiteBishop,
-    BlackBishop,
-    WhiteRook,
-    BlackRook,
-    WhiteQueen,
-    BlackQueen,
-    WhiteKing,
-    BlackKing,
+impl PieceType {
+    // Lowercase letter used for the piece in FEN and UCI notation
+    pub fn to_char(self) -> char {
+        match self {
+            PieceType::Pawn => 'p',
+            PieceType::Knight => 'n',
+            PieceType::Bishop => 'b',
+            PieceType::Rook => 'r',
+            PieceType::Queen => 'q',
+            PieceType::King => 'k',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<PieceType> {
+        match c.to_ascii_lowercase() {
+            'p' => Some(PieceType::Pawn),
+            'n' => Some(PieceType::Knight),
+            'b' => Some(PieceType::Bishop),
+            'r' => Some(PieceType::Rook),
+            'q' => Some(PieceType::Queen),
+            'k' => Some(PieceType::King),
+            _ => None,
+        }
+    }
+}
+
+// Converts a square index (a1 = 0, h8 = 63) to its algebraic name, e.g. "e4"
+pub fn square_to_algebraic(square: u8) -> String {
+    let file = (b'a' + square % 8) as char;
+    let rank = (b'1' + square / 8) as char;
+    format!("{}{}", file, rank)
+}
+
+pub fn algebraic_to_square(name: &str) -> Option<u8> {
+    match name.as_bytes() {
+        [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Some((rank - b'1') * 8 + (file - b'a')),
+        _ => None,
+    }
 }
 
-pub(crate) enum GameResult {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GameResult {
     Draw,
     WhiteWin,
     BlackWin,
 }
 
-enum GameStatus {
+// The score followed by the outcome, e.g. "1-0, White wins"
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GameResult::WhiteWin => "1-0, White wins",
+            GameResult::BlackWin => "0-1, Black wins",
+            GameResult::Draw => "1/2-1/2, draw",
+        })
+    }
+}
+
+// The rule that made a game end in a draw
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DrawReason {
+    Stalemate,
+    InsufficientMaterial,
+    // Claimable draws
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    // Automatic draws
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+    Agreement,
+}
+
+// Completes "draw by ...", e.g. "draw by threefold repetition"
+impl fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DrawReason::Stalemate => "stalemate",
+            DrawReason::InsufficientMaterial => "insufficient material",
+            DrawReason::ThreefoldRepetition => "threefold repetition",
+            DrawReason::FiftyMoveRule => "the fifty-move rule",
+            DrawReason::FivefoldRepetition => "fivefold repetition",
+            DrawReason::SeventyFiveMoveRule => "the seventy-five-move rule",
+            DrawReason::Agreement => "agreement",
+        })
+    }
+}
+
+// How a finished game ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Termination {
+    Checkmate,
+    Resignation,
+    Timeout,
+    Draw(DrawReason),
+}
+
+// Completes "... by ...", e.g. "White wins by checkmate" or "draw by stalemate"
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Termination::Checkmate => f.write_str("checkmate"),
+            Termination::Resignation => f.write_str("resignation"),
+            Termination::Timeout => f.write_str("time forfeit"),
+            Termination::Draw(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+// A clock with a base time per side and an increment added after every move
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    initial: Duration,
+    increment: Duration,
+}
+
+impl TimeControl {
+    pub fn new(initial: Duration, increment: Duration) -> Self {
+        Self { initial, increment }
+    }
+
+    pub fn initial(&self) -> Duration {
+        self.initial
+    }
+
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GameStatus {
     Normal,
     Check,
     Checkmate,
@@ -0,0 +1,388 @@
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::board::Board;
+use crate::move_::Move;
+use crate::protocol::{into_inner, send, RunningSearch};
+use crate::search::{time_budget, SearchInfo, SearchLimits, Searcher};
+use crate::transposition::DEFAULT_HASH_MB;
+use crate::types::Color;
+
+pub const ENGINE_NAME: &str = concat!("rust-chess ", env!("CARGO_PKG_VERSION"));
+pub const ENGINE_AUTHOR: &str = "the rust-chess developers";
+
+const MAX_HASH_MB: usize = 1024;
+const MAX_MULTI_PV: usize = 64;
+
+// How often an infinite search that has finished looks for the stop command
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+// Speaks the Universal Chess Interface: reads a GUI's commands line by line and writes the
+// responses to `output`. Searches run on a thread of their own so that `stop` and `isready`
+// are answered while the engine thinks.
+pub struct UciEngine<W: Write + Send + 'static> {
+    output: Arc<Mutex<W>>,
+    // None while a search has it
+    searcher: Option<Searcher>,
+    search: Option<RunningSearch<()>>,
+    board: Board,
+    turn: Color,
+    // Keys of the positions before the current one, for repetition detection
+    history: Vec<u64>,
+    multi_pv: usize,
+}
+
+impl<W: Write + Send + 'static> UciEngine<W> {
+    pub fn new(output: W) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(output)),
+            searcher: Some(Searcher::new()),
+            search: None,
+            board: Board::new(),
+            turn: Color::White,
+            history: Vec::new(),
+            multi_pv: 1,
+        }
+    }
+
+    // Handles commands until `quit` or the end of the input. At the end of the input a search
+    // with limits is allowed to finish, so that scripted sessions can be piped in.
+    pub fn run(&mut self, input: impl BufRead) -> io::Result<()> {
+        for line in input.lines() {
+            if !self.handle_command(&line?)? {
+                return Ok(());
+            }
+        }
+
+        if self.search.as_ref().is_some_and(RunningSearch::unlimited) {
+            self.stop_search();
+        }
+        self.wait_for_search();
+        Ok(())
+    }
+
+    // Returns false once the engine should quit. Unknown commands are ignored, as UCI asks.
+    pub fn handle_command(&mut self, line: &str) -> io::Result<bool> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, arguments)) = tokens.split_first() else {
+            return Ok(true);
+        };
+
+        match command {
+            "uci" => {
+                self.send(&format!("id name {}", ENGINE_NAME))?;
+                self.send(&format!("id author {}", ENGINE_AUTHOR))?;
+                self.send(&format!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH_MB, MAX_HASH_MB
+                ))?;
+                self.send("option name Threads type spin default 1 min 1 max 1")?;
+                self.send(&format!("option name MultiPV type spin default 1 min 1 max {}", MAX_MULTI_PV))?;
+                self.send("uciok")?;
+            }
+            "isready" => self.send("readyok")?,
+            "ucinewgame" => {
+                self.wait_for_search();
+                self.searcher_mut().clear_hash();
+                self.set_position(Board::new(), Color::White);
+            }
+            "setoption" => self.set_option(arguments)?,
+            "position" => self.position(arguments)?,
+            "go" => self.go(arguments)?,
+            "stop" => self.stop_search(),
+            "quit" => {
+                self.stop_search();
+                return Ok(false);
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    // Waits for the search in progress and gives back the output
+    pub fn into_output(mut self) -> W {
+        self.wait_for_search();
+        into_inner(self.output)
+    }
+
+    fn send(&self, line: &str) -> io::Result<()> {
+        send(&self.output, line)
+    }
+
+    fn searcher_mut(&mut self) -> &mut Searcher {
+        self.searcher.as_mut().expect("no search is running")
+    }
+
+    fn set_position(&mut self, board: Board, turn: Color) {
+        self.board = board;
+        self.turn = turn;
+        self.history.clear();
+    }
+
+    // setoption name <id> [value <x>]
+    fn set_option(&mut self, arguments: &[&str]) -> io::Result<()> {
+        let value_at = arguments.iter().position(|&token| token == "value");
+        let name = arguments[..value_at.unwrap_or(arguments.len())]
+            .iter()
+            .skip_while(|&&token| token == "name")
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let value = value_at.map(|index| arguments[index + 1..].join(" ")).unwrap_or_default();
+
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(megabytes) => {
+                    self.wait_for_search();
+                    self.searcher_mut().set_hash_size(megabytes.clamp(1, MAX_HASH_MB));
+                }
+                Err(_) => self.send(&format!("info string Invalid Hash value '{}'", value))?,
+            },
+            "threads" => {
+                if value != "1" {
+                    self.send("info string Only one thread is supported")?;
+                }
+            }
+            "multipv" => match value.parse::<usize>() {
+                Ok(lines) => self.multi_pv = lines.clamp(1, MAX_MULTI_PV),
+                Err(_) => self.send(&format!("info string Invalid MultiPV value '{}'", value))?,
+            },
+            _ => self.send(&format!("info string Unknown option '{}'", name))?,
+        }
+
+        Ok(())
+    }
+
+    // position startpos|fen <fen> [moves <move>...]. An invalid position leaves the current one.
+    fn position(&mut self, arguments: &[&str]) -> io::Result<()> {
+        let moves_at = arguments.iter().position(|&token| token == "moves").unwrap_or(arguments.len());
+        let (setup, moves) = arguments.split_at(moves_at);
+
+        let (mut board, mut turn) = match setup {
+            ["startpos"] => (Board::new(), Color::White),
+            ["fen", fields @ ..] => {
+                let fen = fields.join(" ");
+                match Board::from_fen(&fen) {
+                    // The active color has been checked by from_fen
+                    Ok(board) => (board, if fields[1] == "b" { Color::Black } else { Color::White }),
+                    Err(error) => return self.send(&format!("info string Invalid FEN: {}", error)),
+                }
+            }
+            _ => return self.send("info string Expected 'position startpos' or 'position fen <fen>'"),
+        };
+
+        let mut history = Vec::with_capacity(moves.len());
+        for text in moves.iter().skip(1) {
+            match Move::from_uci(text, &board, turn) {
+                Ok(move_) => {
+                    history.push(board.hash());
+                    board.make_move(&move_);
+                    turn = turn.opponent();
+                }
+                Err(error) => return self.send(&format!("info string {}", error)),
+            }
+        }
+
+        self.set_position(board, turn);
+        self.history = history;
+        Ok(())
+    }
+
+    // go [depth <plies>] [nodes <n>] [movetime <ms>] [wtime <ms>] [btime <ms>] [winc <ms>]
+    // [binc <ms>] [movestogo <n>] [infinite]
+    fn go(&mut self, arguments: &[&str]) -> io::Result<()> {
+        self.wait_for_search();
+
+        let mut limits = SearchLimits::new().multi_pv(self.multi_pv);
+        let mut infinite = false;
+        let mut clock = [None, None];
+        let mut increment = [Duration::ZERO; 2];
+        let mut moves_to_go = None;
+
+        let mut tokens = arguments.iter();
+        while let Some(&token) = tokens.next() {
+            let mut number = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+            match token {
+                "depth" => limits = number().map_or(limits, |depth| limits.depth(depth as u32)),
+                "nodes" => limits = number().map_or(limits, |nodes| limits.nodes(nodes)),
+                "movetime" => limits = number().map_or(limits, |ms| limits.time(Duration::from_millis(ms))),
+                "wtime" => clock[Color::White as usize] = number().map(Duration::from_millis),
+                "btime" => clock[Color::Black as usize] = number().map(Duration::from_millis),
+                "winc" => increment[Color::White as usize] = number().map_or(Duration::ZERO, Duration::from_millis),
+                "binc" => increment[Color::Black as usize] = number().map_or(Duration::ZERO, Duration::from_millis),
+                "movestogo" => moves_to_go = number().map(|moves| moves as u32),
+                "infinite" => infinite = true,
+                _ => {}
+            }
+        }
+
+        if infinite {
+            limits = SearchLimits::new().multi_pv(self.multi_pv);
+        } else if let Some(time_left) = clock[self.turn as usize] {
+            let budget = time_budget(time_left, increment[self.turn as usize], moves_to_go);
+            if limits.get_time().is_none_or(|time| budget < time) {
+                limits = limits.time(budget);
+            }
+        }
+
+        let searcher = self.searcher.take().expect("no search is running");
+        let output = Arc::clone(&self.output);
+        let board = self.board.clone();
+        let turn = self.turn;
+        let history = self.history.clone();
+
+        let search = RunningSearch::spawn(searcher, infinite, move |searcher| {
+            let result = searcher.search(&board, turn, &history, limits, |info| {
+                let _ = send(&output, &info_line(info));
+            });
+
+            // UCI forbids answering an infinite search before it is stopped. Its flag stays
+            // raised once it is, until the next search starts.
+            if infinite {
+                let stop = searcher.stop_flag();
+                while !stop.load(Ordering::Relaxed) {
+                    thread::sleep(STOP_POLL_INTERVAL);
+                }
+            }
+
+            let best_move = match result.as_ref().map(SearchInfo::pv) {
+                Some([best, ponder, ..]) => format!("bestmove {} ponder {}", best, ponder),
+                Some([best, ..]) => format!("bestmove {}", best),
+                _ => "bestmove 0000".to_string(),
+            };
+            let _ = send(&output, &best_move);
+        });
+
+        self.search = Some(search);
+        Ok(())
+    }
+
+    // Without a search running this does nothing, so a late `stop` cannot cut the next one short
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.searcher = Some(search.stop().0);
+        }
+    }
+
+    fn wait_for_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.searcher = Some(search.join().0);
+        }
+    }
+}
+
+// info depth <d> multipv <k> score cp <x>|mate <n> nodes <n> nps <n> hashfull <n> time <ms> pv <move>...
+fn info_line(info: &SearchInfo) -> String {
+    let score = match info.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", info.score()),
+    };
+    let millis = info.elapsed().as_millis() as u64;
+    let nps = info.nodes() * 1000 / millis.max(1);
+    let pv: Vec<String> = info.pv().iter().map(Move::to_string).collect();
+
+    format!(
+        "info depth {} multipv {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+        info.depth(),
+        info.multi_pv(),
+        score,
+        info.nodes(),
+        nps,
+        info.hashfull(),
+        millis,
+        pv.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(input: &str) -> Vec<String> {
+        let mut engine = UciEngine::new(Vec::new());
+        engine.run(input.as_bytes()).unwrap();
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        output.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn identifies_itself() {
+        let lines = session("uci\nisready\n");
+
+        assert_eq!(lines[0], format!("id name {}", ENGINE_NAME));
+        assert!(lines.contains(&"option name Hash type spin default 16 min 1 max 1024".to_string()));
+        assert_eq!(lines[lines.len() - 2..], ["uciok", "readyok"]);
+    }
+
+    #[test]
+    fn searches_positions() {
+        let lines = session("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 3\n");
+        assert!(lines[0].starts_with("info depth 1 multipv 1 score mate 1 nodes "));
+        assert!(lines[0].ends_with(" pv a1a8"));
+        assert_eq!(lines.last().unwrap(), "bestmove a1a8");
+
+        // After 1. f3 e5 2. g4 black mates with Qh4
+        let lines = session("ucinewgame\nposition startpos moves f2f3 e7e5 g2g4\ngo depth 2 wtime 1000 btime 1000\n");
+        assert_eq!(lines.last().unwrap(), "bestmove d8h4");
+
+        let lines = session("position fen 7k/5Q2/6K1/8/8/8/8/8 b - - 0 1\ngo depth 1\n");
+        assert_eq!(lines, ["bestmove 0000"]);
+    }
+
+    #[test]
+    fn reports_several_variations() {
+        let lines = session("setoption name MultiPV value 3\nposition startpos\ngo depth 1\n");
+        let depth_one: Vec<&String> = lines.iter().filter(|line| line.starts_with("info depth 1 ")).collect();
+
+        assert_eq!(depth_one.len(), 3);
+        for (index, line) in depth_one.iter().enumerate() {
+            assert!(line.contains(&format!(" multipv {} ", index + 1)));
+        }
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn stops_infinite_searches() {
+        let mut engine = UciEngine::new(Vec::new());
+        for command in ["position startpos moves e2e4", "go infinite", "isready"] {
+            assert!(engine.handle_command(command).unwrap());
+        }
+        thread::sleep(Duration::from_millis(50));
+        assert!(!engine.handle_command("quit").unwrap());
+
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        assert!(output.contains("readyok\n"));
+        assert!(output.lines().last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn ignores_stop_after_bestmove() {
+        let mut engine = UciEngine::new(Vec::new());
+        engine.handle_command("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        engine.handle_command("go depth 2").unwrap();
+        while !String::from_utf8_lossy(&engine.output.lock().unwrap()).contains("bestmove") {
+            thread::sleep(Duration::from_millis(1));
+        }
+        engine.handle_command("stop").unwrap();
+        engine.handle_command("go depth 3").unwrap();
+
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        let best_moves: Vec<&str> = output.lines().filter(|line| line.starts_with("bestmove ")).collect();
+        assert_eq!(best_moves, ["bestmove a1a8", "bestmove a1a8"]);
+    }
+
+    #[test]
+    fn reports_bad_input() {
+        let lines = session(
+            "position fen 8/8/8 w - - 0 1\nposition startpos moves e2e5\nsetoption name Style value Aggressive\n",
+        );
+
+        assert!(lines[0].starts_with("info string Invalid FEN"));
+        assert!(lines[1].starts_with("info string Illegal move 'e2e5'"));
+        assert_eq!(lines[2], "info string Unknown option 'Style'");
+    }
+}
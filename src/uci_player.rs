@@ -0,0 +1,326 @@
+use std::ffi::OsStr;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::move_::Move;
+use crate::player::{Player, PlayerAction, PlayerView};
+use crate::search::SearchLimits;
+use crate::types::Color;
+
+// How long UciEnginePlayer::new lets the engine think on a move in games without a clock
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(1);
+
+// How long the engine gets to exit after `quit` before it is killed
+const QUIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+// How long the engine gets to answer `uci` and `isready`
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// How long past its time for the move the engine may take to send `bestmove`, and how
+// long it gets in all when it searches to a depth or number of nodes without a time limit
+const MOVE_MARGIN: Duration = Duration::from_secs(1);
+const UNTIMED_MOVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+// An external engine speaking UCI, e.g. Stockfish, running as a child process. It is given
+// the game's clock when there is one and its limits otherwise. Anything going wrong with the
+// engine, from a crash or a missed deadline to an illegal move, resigns the game.
+pub struct UciEnginePlayer {
+    child: Child,
+    input: ChildStdin,
+    // Lines of the engine's output, read on a thread of their own so that waiting can time out
+    output: Receiver<io::Result<String>>,
+    name: Option<String>,
+    limits: SearchLimits,
+}
+
+impl UciEnginePlayer {
+    // Starts the engine and waits for it to complete the UCI handshake
+    pub fn new(program: impl AsRef<OsStr>) -> io::Result<Self> {
+        Self::with_command(Command::new(program))
+    }
+
+    // Like new, for an engine that needs arguments or a working directory
+    pub fn with_command(mut command: Command) -> io::Result<Self> {
+        let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let input = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        // Ends when the engine closes its output or the player is gone
+        let (sender, output) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let failed = line.is_err();
+                if sender.send(line).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        let mut player =
+            Self { child, input, output, name: None, limits: SearchLimits::new().time(DEFAULT_MOVE_TIME) };
+
+        player.send("uci")?;
+        let mut name = None;
+        player.read_until(REPLY_TIMEOUT, |line| {
+            if let Some(id) = line.strip_prefix("id name ") {
+                name = Some(id.trim().to_string());
+            }
+            line == "uciok"
+        })?;
+        player.name = name;
+
+        player.send("ucinewgame")?;
+        player.synchronize()?;
+        Ok(player)
+    }
+
+    // How long the engine thinks when the game has no clock; depth and nodes limits always apply
+    pub fn limits(mut self, limits: SearchLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    // The name the engine gave in the handshake
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.send(&format!("setoption name {} value {}", name, value))?;
+        self.synchronize()
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.input, "{}", command)?;
+        self.input.flush()
+    }
+
+    // Reads lines until one is accepted and returns it. An engine that sends none within
+    // `timeout` is killed.
+    fn read_until(&mut self, timeout: Duration, mut accept: impl FnMut(&str) -> bool) -> io::Result<String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let line = match self.output.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => line?,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the engine closed its output"));
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let _ = self.child.kill();
+                    let _ = self.child.wait();
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "the engine did not answer in time"));
+                }
+            };
+            if accept(line.trim()) {
+                return Ok(line.trim().to_string());
+            }
+        }
+    }
+
+    fn synchronize(&mut self) -> io::Result<()> {
+        self.send("isready")?;
+        self.read_until(REPLY_TIMEOUT, |line| line == "readyok").map(|_| ())
+    }
+
+    fn go_command(&self, view: &PlayerView) -> String {
+        let mut command = "go".to_string();
+        if let Some(depth) = self.limits.get_depth() {
+            command.push_str(&format!(" depth {}", depth));
+        }
+        if let Some(nodes) = self.limits.get_nodes() {
+            command.push_str(&format!(" nodes {}", nodes));
+        }
+
+        if let (Some(white), Some(black)) = (view.time_left(Color::White), view.time_left(Color::Black)) {
+            let increment = view.time_control().map_or(Duration::ZERO, |time_control| time_control.increment());
+            command.push_str(&format!(
+                " wtime {} btime {} winc {} binc {}",
+                white.as_millis(),
+                black.as_millis(),
+                increment.as_millis(),
+                increment.as_millis()
+            ));
+        } else if let Some(time) = self.limits.get_time() {
+            command.push_str(&format!(" movetime {}", time.as_millis()));
+        } else if command == "go" {
+            // A bare go would let the engine think forever
+            command.push_str(&format!(" movetime {}", DEFAULT_MOVE_TIME.as_millis()));
+        }
+
+        command
+    }
+
+    // How long to wait for `bestmove`: the time the engine has for the move, plus a margin
+    fn move_timeout(&self, view: &PlayerView) -> Duration {
+        match view.time_left(view.turn()).or(self.limits.get_time()) {
+            Some(time) => time + MOVE_MARGIN,
+            None if self.limits.get_depth().is_some() || self.limits.get_nodes().is_some() => UNTIMED_MOVE_TIMEOUT,
+            // go_command falls back to this move time
+            None => DEFAULT_MOVE_TIME + MOVE_MARGIN,
+        }
+    }
+
+    // The engine's move, or None when it has none or plays an illegal one
+    fn best_move(&mut self, view: &PlayerView) -> io::Result<Option<Move>> {
+        let moves: Vec<String> = view.move_history().iter().map(Move::to_string).collect();
+        let position = if moves.is_empty() {
+            format!("position fen {}", view.start_fen())
+        } else {
+            format!("position fen {} moves {}", view.start_fen(), moves.join(" "))
+        };
+        self.send(&position)?;
+        self.send(&self.go_command(view))?;
+
+        let line = self.read_until(self.move_timeout(view), |line| line.starts_with("bestmove"))?;
+        let best_move = line.split_whitespace().nth(1).unwrap_or_default();
+        Ok(view.legal_moves().iter().copied().find(|move_| move_.to_string() == best_move))
+    }
+}
+
+impl Player for UciEnginePlayer {
+    fn select_move(&mut self, view: &PlayerView) -> PlayerAction {
+        match self.best_move(view) {
+            Ok(Some(move_)) => PlayerAction::Move(move_),
+            Ok(None) | Err(_) => PlayerAction::Resign,
+        }
+    }
+}
+
+impl Drop for UciEnginePlayer {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(Some(_)) | Err(_) => return,
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::STARTING_FEN;
+    use crate::game::Game;
+    use crate::player::{CliPlayer, RandomPlayer};
+    use crate::types::{GameResult, TimeControl};
+
+    // A shell script that answers like a UCI engine, playing `reply` to every `go`, or staying
+    // silent when it is empty, and logging the position and go commands it receives. Its
+    // directory is removed when it is dropped.
+    #[cfg(unix)]
+    struct StandInEngine {
+        directory: std::path::PathBuf,
+    }
+
+    #[cfg(unix)]
+    impl StandInEngine {
+        fn new(name: &str, reply: &str) -> Self {
+            use std::os::unix::fs::PermissionsExt;
+            use std::sync::atomic::{AtomicUsize, Ordering};
+
+            static COUNT: AtomicUsize = AtomicUsize::new(0);
+            let unique = format!("{}-{}-{}", name, std::process::id(), COUNT.fetch_add(1, Ordering::Relaxed));
+            let directory = std::env::temp_dir().join(format!("rust-chess-{}", unique));
+            std::fs::create_dir_all(&directory).unwrap();
+            let stand_in = Self { directory };
+
+            let answer = if reply.is_empty() {
+                ":".to_string()
+            } else {
+                format!("echo 'info depth 1 score cp 12 pv {0}'; echo 'bestmove {0}'", reply)
+            };
+            let text = r#"#!/bin/sh
+while read -r line; do
+    case "$line" in
+        uci) echo 'id name Stand-in 1.0'; echo uciok ;;
+        isready) echo readyok ;;
+        position*|setoption*) echo "$line" >> 'LOG' ;;
+        go*) echo "$line" >> 'LOG'; ANSWER ;;
+        quit) exit 0 ;;
+    esac
+done
+"#
+            .replace("LOG", &stand_in.log().display().to_string())
+            .replace("ANSWER", &answer);
+            std::fs::write(stand_in.script(), text).unwrap();
+            std::fs::set_permissions(stand_in.script(), std::fs::Permissions::from_mode(0o755)).unwrap();
+            stand_in
+        }
+
+        fn script(&self) -> std::path::PathBuf {
+            self.directory.join("engine.sh")
+        }
+
+        fn log(&self) -> std::path::PathBuf {
+            self.directory.join("commands.log")
+        }
+    }
+
+    #[cfg(unix)]
+    impl Drop for StandInEngine {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn uci_engine_player_talks_to_engines() {
+        let stand_in = StandInEngine::new("uci-player", "e7e5");
+        let mut engine = UciEnginePlayer::new(stand_in.script()).unwrap();
+        assert_eq!(engine.name(), Some("Stand-in 1.0"));
+        engine.set_option("Hash", "8").unwrap();
+
+        let mut game = Game::new(Box::new(CliPlayer::with_io("e4\nNf3\n".as_bytes(), Vec::new())), Box::new(engine));
+        for _ in 0..3 {
+            game.step().unwrap();
+        }
+        game.set_time_control(TimeControl::new(Duration::from_secs(60), Duration::from_secs(2)));
+        game.step().unwrap();
+
+        // The stand-in repeats itself, which is illegal the second time
+        let history: Vec<String> = game.move_history().iter().map(|move_| move_.to_string()).collect();
+        assert_eq!(history, ["e2e4", "e7e5", "g1f3"]);
+        assert_eq!(game.get_game_result(), Some(GameResult::WhiteWin));
+        drop(game);
+
+        let log = std::fs::read_to_string(stand_in.log()).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines[0], "setoption name Hash value 8");
+        assert_eq!(lines[1], format!("position fen {} moves e2e4", STARTING_FEN));
+        assert_eq!(lines[2], "go movetime 1000");
+        assert_eq!(lines[3], format!("position fen {} moves e2e4 e7e5 g1f3", STARTING_FEN));
+        assert!(lines[4].starts_with("go wtime 60000 btime "));
+        assert!(lines[4].ends_with(" winc 2000 binc 2000"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn uci_engine_player_resigns_when_the_engine_hangs() {
+        let stand_in = StandInEngine::new("hanging-engine", "");
+        let limits = SearchLimits::new().time(Duration::from_millis(50));
+        let engine = UciEnginePlayer::new(stand_in.script()).unwrap().limits(limits);
+
+        let started = Instant::now();
+        let mut game = Game::new(Box::new(engine), Box::new(RandomPlayer));
+        game.step().unwrap();
+
+        assert_eq!(game.get_game_result(), Some(GameResult::BlackWin));
+        assert!(started.elapsed() < Duration::from_millis(50) + MOVE_MARGIN * 2);
+    }
+
+    #[test]
+    fn uci_engine_player_needs_an_engine() {
+        assert!(UciEnginePlayer::new("/nonexistent/rust-chess-engine").is_err());
+    }
+}
@@ -0,0 +1,488 @@
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::board::{Board, UndoInfo};
+use crate::move_::Move;
+use crate::move_validator::MoveValidator;
+use crate::protocol::{into_inner, send, RunningSearch};
+use crate::search::{time_budget, SearchInfo, SearchLimits, Searcher};
+use crate::types::Color;
+use crate::uci::ENGINE_NAME;
+
+// Scores of mates as CECP shows them, plus or minus the moves to mate
+const XBOARD_MATE_SCORE: i32 = 100_000;
+
+// Speaks the Chess Engine Communication Protocol version 2 used by XBoard and WinBoard: reads
+// commands line by line and writes the responses to `output`. The engine thinks on a thread of
+// its own so that `?`, `time` and `otim` are read while it does; its move is played on the
+// board when the next command that needs the board comes in.
+pub struct XBoardEngine<W: Write + Send + 'static> {
+    output: Arc<Mutex<W>>,
+    move_validator: MoveValidator,
+    // None while a search has it
+    searcher: Option<Searcher>,
+    // The search hands back the move it played
+    search: Option<RunningSearch<Option<Move>>>,
+    // Set when the search is given up, e.g. for `force` or `new`: no move is played
+    aborted: Arc<AtomicBool>,
+    board: Board,
+    turn: Color,
+    // Every move played with its undo information, and the keys of the positions before them
+    moves: Vec<(Move, UndoInfo)>,
+    history: Vec<u64>,
+    // The side the engine plays, None in force mode
+    engine_color: Option<Color>,
+    post: bool,
+    // Set by `sd` and `st`
+    depth: Option<u32>,
+    move_time: Option<Duration>,
+    // Set by `level`: moves per time control, 0 for the whole game, and the increment
+    moves_per_session: u32,
+    increment: Duration,
+    // Set by `time`, the engine's remaining time
+    clock: Option<Duration>,
+}
+
+impl<W: Write + Send + 'static> XBoardEngine<W> {
+    pub fn new(output: W) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(output)),
+            move_validator: MoveValidator::new(),
+            searcher: Some(Searcher::new()),
+            search: None,
+            aborted: Arc::new(AtomicBool::new(false)),
+            board: Board::new(),
+            turn: Color::White,
+            moves: Vec::new(),
+            history: Vec::new(),
+            engine_color: Some(Color::Black),
+            post: false,
+            depth: None,
+            move_time: None,
+            moves_per_session: 0,
+            increment: Duration::ZERO,
+            clock: None,
+        }
+    }
+
+    // Handles commands until `quit` or the end of the input. At the end of the input the engine
+    // finishes thinking, or moves now without any limits, so that scripted sessions can be piped in.
+    pub fn run(&mut self, input: impl BufRead) -> io::Result<()> {
+        for line in input.lines() {
+            if !self.handle_command(&line?)? {
+                return Ok(());
+            }
+        }
+
+        if self.search.as_ref().is_some_and(RunningSearch::unlimited) {
+            self.stop_search();
+        }
+        self.finish_search();
+        Ok(())
+    }
+
+    // Returns false once the engine should quit
+    pub fn handle_command(&mut self, line: &str) -> io::Result<bool> {
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+
+        // Commands that are answered while the engine thinks
+        match command {
+            "" | "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer" | "name" | "rating"
+            | "ics" | "draw" => return Ok(true),
+            "post" => {
+                self.post = true;
+                return Ok(true);
+            }
+            "nopost" => {
+                self.post = false;
+                return Ok(true);
+            }
+            "time" | "otim" => {
+                let Ok(centiseconds) = argument.parse::<u64>() else {
+                    self.send(&format!("Error (invalid time): {}", line))?;
+                    return Ok(true);
+                };
+                if command == "time" {
+                    self.clock = Some(Duration::from_millis(centiseconds * 10));
+                }
+                return Ok(true);
+            }
+            "?" => {
+                self.stop_search();
+                return Ok(true);
+            }
+            _ => {}
+        }
+
+        // Commands that end the thinking without a move
+        if matches!(command, "new" | "force" | "setboard" | "undo" | "remove" | "result" | "quit") {
+            self.abort_search();
+        }
+        self.finish_search();
+
+        match command {
+            "protover" => {
+                self.send(&format!(
+                    "feature myname=\"{}\" setboard=1 usermove=1 ping=1 playother=1 san=0 colors=0 \
+                     sigint=0 sigterm=0 reuse=1 analyze=0 variants=\"normal\" done=1",
+                    ENGINE_NAME
+                ))?;
+            }
+            "ping" => self.send(&format!("pong {}", argument))?,
+            "new" => {
+                self.set_position(Board::new(), Color::White);
+                self.engine_color = Some(Color::Black);
+                self.depth = None;
+                self.move_time = None;
+                self.clock = None;
+                self.searcher_mut().clear_hash();
+            }
+            "setboard" => match Board::from_fen(argument) {
+                Ok(board) => {
+                    // The active color has been checked by from_fen
+                    let turn = if argument.split_whitespace().nth(1) == Some("b") { Color::Black } else { Color::White };
+                    self.set_position(board, turn);
+                }
+                Err(error) => self.send(&format!("tellusererror Illegal position: {}", error))?,
+            },
+            "usermove" => self.user_move(argument)?,
+            "go" => {
+                self.engine_color = Some(self.turn);
+                self.think()?;
+            }
+            "playother" => self.engine_color = Some(self.turn.opponent()),
+            "force" | "result" => self.engine_color = None,
+            "undo" => self.take_back(1),
+            "remove" => self.take_back(2),
+            "level" => self.set_level(argument, line)?,
+            "st" => match argument.parse::<f64>().ok().and_then(|seconds| Duration::try_from_secs_f64(seconds).ok()) {
+                Some(move_time) if !move_time.is_zero() => self.move_time = Some(move_time),
+                _ => self.send(&format!("Error (invalid time): {}", line))?,
+            },
+            "sd" => match argument.parse::<u32>() {
+                Ok(depth) if depth > 0 => self.depth = Some(depth),
+                _ => self.send(&format!("Error (invalid depth): {}", line))?,
+            },
+            "quit" => return Ok(false),
+            _ => self.send(&format!("Error (unknown command): {}", command))?,
+        }
+
+        Ok(true)
+    }
+
+    // Waits for the engine to finish thinking and gives back the output
+    pub fn into_output(mut self) -> W {
+        self.finish_search();
+        into_inner(self.output)
+    }
+
+    fn send(&self, line: &str) -> io::Result<()> {
+        send(&self.output, line)
+    }
+
+    fn searcher_mut(&mut self) -> &mut Searcher {
+        self.searcher.as_mut().expect("no search is running")
+    }
+
+    fn set_position(&mut self, board: Board, turn: Color) {
+        self.board = board;
+        self.turn = turn;
+        self.moves.clear();
+        self.history.clear();
+    }
+
+    fn play(&mut self, move_: Move) {
+        self.history.push(self.board.hash());
+        let undo = self.board.make_move(&move_);
+        self.moves.push((move_, undo));
+        self.turn = self.turn.opponent();
+    }
+
+    fn take_back(&mut self, plies: usize) {
+        for _ in 0..plies.min(self.moves.len()) {
+            let (move_, undo) = self.moves.pop().expect("checked against the number of moves");
+            self.board.unmake_move(&move_, &undo);
+            self.history.pop();
+            self.turn = self.turn.opponent();
+        }
+    }
+
+    fn user_move(&mut self, text: &str) -> io::Result<()> {
+        match Move::from_uci(text, &self.board, self.turn) {
+            Ok(move_) => {
+                self.play(move_);
+                if self.engine_color == Some(self.turn) {
+                    self.think()?;
+                }
+                Ok(())
+            }
+            Err(_) => self.send(&format!("Illegal move: {}", text)),
+        }
+    }
+
+    // level <moves per control> <minutes or minutes:seconds> <increment in seconds>
+    fn set_level(&mut self, argument: &str, line: &str) -> io::Result<()> {
+        let fields: Vec<&str> = argument.split_whitespace().collect();
+        let [moves, base, increment] = fields[..] else {
+            return self.send(&format!("Error (invalid level): {}", line));
+        };
+
+        let (minutes, seconds) = base.split_once(':').unwrap_or((base, "0"));
+        let valid = moves.parse::<u32>().ok().zip(minutes.parse::<u64>().ok()).zip(seconds.parse::<u64>().ok());
+        let increment = increment.parse::<f64>().ok().and_then(|seconds| Duration::try_from_secs_f64(seconds).ok());
+
+        match (valid, increment) {
+            (Some(((moves, minutes), seconds)), Some(increment)) => {
+                self.moves_per_session = moves;
+                self.increment = increment;
+                self.move_time = None;
+                // The clock starts at the base time until the GUI sends `time`
+                self.clock = Some(Duration::from_secs(minutes.saturating_mul(60).saturating_add(seconds)));
+                Ok(())
+            }
+            _ => self.send(&format!("Error (invalid level): {}", line)),
+        }
+    }
+
+    fn limits(&self) -> SearchLimits {
+        let mut limits = SearchLimits::new();
+        if let Some(depth) = self.depth {
+            limits = limits.depth(depth);
+        }
+
+        if let Some(move_time) = self.move_time {
+            limits.time(move_time)
+        } else if let Some(clock) = self.clock {
+            let moves_to_go = (self.moves_per_session > 0).then(|| {
+                let engine_moves = (self.moves.len() / 2) as u32;
+                self.moves_per_session - engine_moves % self.moves_per_session
+            });
+            limits.time(time_budget(clock, self.increment, moves_to_go))
+        } else {
+            limits
+        }
+    }
+
+    // Reports the end of the game when the side to move has no legal move
+    fn announce_game_end(&self) -> io::Result<bool> {
+        if !self.move_validator.get_legal_moves(&self.board, self.turn).is_empty() {
+            return Ok(false);
+        }
+
+        let result = match (self.board.in_check(self.turn), self.turn) {
+            (true, Color::White) => "0-1 {Black mates}",
+            (true, Color::Black) => "1-0 {White mates}",
+            (false, _) => "1/2-1/2 {Stalemate}",
+        };
+        self.send(result)?;
+        Ok(true)
+    }
+
+    fn think(&mut self) -> io::Result<()> {
+        if self.announce_game_end()? {
+            return Ok(());
+        }
+
+        let limits = self.limits();
+        let unlimited = limits == SearchLimits::new();
+        let searcher = self.searcher.take().expect("no search is running");
+        self.aborted = Arc::new(AtomicBool::new(false));
+        let aborted = Arc::clone(&self.aborted);
+        let output = Arc::clone(&self.output);
+        let board = self.board.clone();
+        let turn = self.turn;
+        let history = self.history.clone();
+        let post = self.post;
+
+        let search = RunningSearch::spawn(searcher, unlimited, move |searcher| {
+            let result = searcher.search(&board, turn, &history, limits, |info| {
+                if post {
+                    let _ = send(&output, &thinking_line(info));
+                }
+            });
+
+            // Holding the output while looking at the flag, a move is either sent and played or neither
+            let mut output = output.lock().unwrap_or_else(PoisonError::into_inner);
+            let move_ = result.filter(|_| !aborted.load(Ordering::Relaxed)).map(|info| info.best_move());
+            if let Some(move_) = move_ {
+                let _ = writeln!(output, "move {}", move_).and_then(|_| output.flush());
+            }
+            move_
+        });
+
+        self.search = Some(search);
+        Ok(())
+    }
+
+    // Gives up the thinking without playing a move
+    fn abort_search(&mut self) {
+        if self.search.is_some() {
+            let _output = self.output.lock().unwrap_or_else(PoisonError::into_inner);
+            self.aborted.store(true, Ordering::Relaxed);
+        }
+        self.stop_search();
+    }
+
+    // Makes the engine move now. Without a search running this does nothing.
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            let result = search.stop();
+            self.play_search_result(result);
+        }
+    }
+
+    // Waits for the engine to finish thinking and plays its move
+    fn finish_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            let result = search.join();
+            self.play_search_result(result);
+        }
+    }
+
+    fn play_search_result(&mut self, (searcher, move_): (Searcher, Option<Move>)) {
+        self.searcher = Some(searcher);
+        if let Some(move_) = move_ {
+            self.play(move_);
+            let _ = self.announce_game_end();
+        }
+    }
+}
+
+// <depth> <score> <time in centiseconds> <nodes> <principal variation>
+fn thinking_line(info: &SearchInfo) -> String {
+    let score = match info.mate_in() {
+        Some(moves) if moves > 0 => XBOARD_MATE_SCORE + moves,
+        Some(moves) => -XBOARD_MATE_SCORE + moves,
+        None => info.score(),
+    };
+    let pv: Vec<String> = info.pv().iter().map(Move::to_string).collect();
+
+    format!("{} {} {} {} {}", info.depth(), score, info.elapsed().as_millis() / 10, info.nodes(), pv.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn session(input: &str) -> Vec<String> {
+        let mut engine = XBoardEngine::new(Vec::new());
+        engine.run(input.as_bytes()).unwrap();
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        output.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn negotiates_features() {
+        let lines = session("xboard\nprotover 2\nping 7\n");
+
+        assert!(lines[0].starts_with("feature myname=\"rust-chess "));
+        assert!(lines[0].contains(" setboard=1 usermove=1 ping=1 "));
+        assert!(lines[0].ends_with(" done=1"));
+        assert_eq!(lines[1], "pong 7");
+    }
+
+    #[test]
+    fn answers_user_moves() {
+        // Black to move after 1. f3 e5 2. g4 mates with Qh4
+        let lines = session("new\nforce\nsd 2\nusermove f2f3\nusermove e7e5\nusermove g2g4\ngo\n");
+        assert_eq!(lines, ["move d8h4", "0-1 {Black mates}"]);
+
+        let lines = session("new\npost\nsd 2\nusermove e2e4\n");
+        assert!(lines[0].starts_with("1 "));
+        assert!(lines[1].starts_with("2 "));
+        assert!(lines[2].starts_with("move "));
+
+        let lines = session("new\nusermove e2e5\nusermove e1e2\nfoo\n");
+        assert_eq!(lines, ["Illegal move: e2e5", "Illegal move: e1e2", "Error (unknown command): foo"]);
+    }
+
+    #[test]
+    fn edits_and_takes_back_positions() {
+        let mut engine = XBoardEngine::new(Vec::new());
+        let commands = "new\nforce\nusermove e2e4\nusermove e7e5\nusermove g1f3\nundo\nremove\n";
+        for command in commands.lines() {
+            engine.handle_command(command).unwrap();
+        }
+        assert_eq!(engine.moves.len(), 0);
+        assert_eq!(engine.board, Board::new());
+
+        engine.handle_command("setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        engine.handle_command("st 1").unwrap();
+        engine.handle_command("go").unwrap();
+        engine.handle_command("ping 1").unwrap();
+        assert_eq!(engine.turn, Color::Black);
+
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        assert_eq!(output, "move a1a8\n1-0 {White mates}\npong 1\n");
+    }
+
+    #[test]
+    fn keeps_to_time_controls() {
+        let mut engine = XBoardEngine::new(Vec::new());
+        for command in ["new", "level 40 5 0", "time 30000", "otim 30000"] {
+            engine.handle_command(command).unwrap();
+        }
+        assert_eq!(engine.limits().get_time(), Some(Duration::from_millis(300_000 / 30)));
+
+        engine.handle_command("st 2.5").unwrap();
+        assert_eq!(engine.limits().get_time(), Some(Duration::from_millis(2500)));
+
+        engine.handle_command("level 0 2:30 1").unwrap();
+        engine.handle_command("time 1000").unwrap();
+        assert_eq!(engine.limits().get_time(), Some(Duration::from_secs(10) / 30 + Duration::from_millis(750)));
+
+        // Without `time` the clock starts at the base time of the level
+        engine.handle_command("new").unwrap();
+        engine.handle_command("level 40 5 0").unwrap();
+        assert_eq!(engine.limits().get_time(), Some(Duration::from_secs(300) / 30));
+        let lines = session("new\nlevel 40 0:03 0\ngo\n");
+        assert!(lines[0].starts_with("move "), "{:?}", lines);
+
+        let lines = session("level 40\ntime fast\nst 1e300\nlevel 0 5 1e300\n");
+        assert_eq!(
+            lines,
+            [
+                "Error (invalid level): level 40",
+                "Error (invalid time): time fast",
+                "Error (invalid time): st 1e300",
+                "Error (invalid level): level 0 5 1e300"
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_the_next_search_alone_after_a_move() {
+        let mut engine = XBoardEngine::new(Vec::new());
+        engine.handle_command("sd 3").unwrap();
+        engine.handle_command("go").unwrap();
+        while !String::from_utf8_lossy(&engine.output.lock().unwrap()).contains("move ") {
+            thread::sleep(Duration::from_millis(1));
+        }
+        for command in ["?", "result 1-0 {x}", "new", "force", "setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "go"] {
+            engine.handle_command(command).unwrap();
+        }
+
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        assert!(output.ends_with("\nmove a1a8\n1-0 {White mates}\n"));
+    }
+
+    #[test]
+    fn moves_now_when_asked() {
+        let mut engine = XBoardEngine::new(Vec::new());
+
+        // Without any time control the engine would think forever
+        for command in ["new", "go", "?", "ping 2"] {
+            engine.handle_command(command).unwrap();
+        }
+        assert_eq!(engine.moves.len(), 1);
+
+        let output = String::from_utf8(engine.into_output()).unwrap();
+        assert!(output.starts_with("move "));
+        assert!(output.ends_with("\npong 2\n"));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::types::{Color, PieceType};
+
+// Random keys for Zobrist hashing, generated at compile time from a fixed seed so that
+// position keys are the same on every run
+const KEY_COUNT: usize = 12 * 64 + 4 + 8 + 1;
+
+const KEYS: [u64; KEY_COUNT] = {
+    let mut keys = [0u64; KEY_COUNT];
+    let mut state = 0x2545F4914F6CDD1Du64;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        // SplitMix64
+        state = state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+};
+
+const CASTLING_OFFSET: usize = 12 * 64;
+const EN_PASSANT_OFFSET: usize = CASTLING_OFFSET + 4;
+const BLACK_TO_MOVE_OFFSET: usize = EN_PASSANT_OFFSET + 8;
+
+pub(crate) fn piece_key(color: Color, piece_type: PieceType, square: u8) -> u64 {
+    KEYS[(color as usize * 6 + piece_type as usize) * 64 + square as usize]
+}
+
+// Keys for the white kingside, white queenside, black kingside and black queenside rights
+pub(crate) fn castling_key(index: usize) -> u64 {
+    KEYS[CASTLING_OFFSET + index]
+}
+
+// En passant squares are keyed by file only, the rank follows from the side to move
+pub(crate) fn en_passant_key(square: u8) -> u64 {
+    KEYS[EN_PASSANT_OFFSET + (square % 8) as usize]
+}
+
+pub(crate) fn black_to_move_key() -> u64 {
+    KEYS[BLACK_TO_MOVE_OFFSET]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::board::Board;
+    use crate::move_::Move;
+    use crate::move_validator::MoveValidator;
+
+    fn play(fen: &str, turn: Color, ucis: &[&str]) -> Board {
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut turn = turn;
+        for uci in ucis {
+            board.make_move(&Move::from_uci(uci, &board, turn).unwrap());
+            turn = turn.opponent();
+        }
+        board
+    }
+
+    #[test]
+    fn keys_are_distinct() {
+        assert_eq!(KEYS.iter().collect::<HashSet<_>>().len(), KEY_COUNT);
+    }
+
+    #[test]
+    fn transpositions_hash_equally() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let one = play(start, Color::White, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+        let other = play(start, Color::White, &["b1c3", "b8c6", "g1f3", "g8f6"]);
+
+        assert_eq!(one.hash(), other.hash());
+        let fen = "r1bqkb1r/pppppppp/2n2n2/8/8/2N2N2/PPPPPPPP/R1BQKB1R w KQkq - 4 3";
+        assert_eq!(one.hash(), Board::from_fen(fen).unwrap().hash());
+
+        // Losing the castling rights on the way makes it a different position
+        let shuffled = play(start, Color::White, &["g1f3", "g8f6", "h1g1", "b8c6", "g1h1", "f6g8", "b1c3", "g8f6"]);
+        assert_ne!(shuffled.hash(), one.hash());
+    }
+
+    #[test]
+    fn unmake_restores_the_hash() {
+        let validator = MoveValidator::new();
+
+        // Castling, captures, en passant and promotions
+        for (fen, turn) in [
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", Color::White),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", Color::White),
+            ("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", Color::Black),
+        ] {
+            let mut board = Board::from_fen(fen).unwrap();
+            let key = board.hash();
+
+            for move_ in validator.get_legal_moves(&board, turn) {
+                let undo = board.make_move(&move_);
+                assert_eq!(board.hash(), board.compute_hash(turn.opponent()), "{} in {}", move_, fen);
+                assert_ne!(board.hash(), key, "{} in {}", move_, fen);
+
+                board.unmake_move(&move_, &undo);
+                assert_eq!(board.hash(), key, "{} in {}", move_, fen);
+            }
+        }
+    }
+
+    #[test]
+    fn state_beyond_the_pieces_changes_the_key() {
+        let key = |fen: &str| Board::from_fen(fen).unwrap().hash();
+
+        // Side to move
+        assert_ne!(key("4k3/8/8/8/8/8/8/4K3 w - -"), key("4k3/8/8/8/8/8/8/4K3 b - -"));
+
+        // Each castling right
+        let all_rights = key("r3k2r/8/8/8/8/8/8/R3K2R w KQkq -");
+        let keys: HashSet<u64> = ["KQkq", "Qkq", "Kkq", "KQq", "KQk", "-"]
+            .iter()
+            .map(|rights| key(&format!("r3k2r/8/8/8/8/8/8/R3K2R w {} -", rights)))
+            .collect();
+        assert_eq!(keys.len(), 6);
+        assert!(keys.contains(&all_rights));
+
+        // The en passant square, by its file
+        let without = key("4k3/8/8/3pPp2/8/8/8/4K3 w - -");
+        let on_d6 = key("4k3/8/8/3pPp2/8/8/8/4K3 w - d6");
+        let on_f6 = key("4k3/8/8/3pPp2/8/8/8/4K3 w - f6");
+        assert_ne!(without, on_d6);
+        assert_ne!(without, on_f6);
+        assert_ne!(on_d6, on_f6);
+    }
+}